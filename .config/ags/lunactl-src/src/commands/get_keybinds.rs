@@ -144,29 +144,29 @@ fn get_binds_recursive(
         let line = &lines[*line_num];
         
         // Check for section headers
-        if let Some(m) = title_re.find(line) {
-            if m.start() == 0 {
-                let heading_scope = m.end();
-                
-                // If we're at a higher or equal level, go up a level
-                if heading_scope <= current_scope {
-                    return current_section;
-                }
-                
-                let section_name = line[heading_scope..].trim();
-                *line_num += 1;
-                
-                // Create a new section and recursively parse its contents
-                let child_section = get_binds_recursive(
-                    lines,
-                    line_num,
-                    heading_scope,
-                    Section::new(section_name)
-                );
-                
-                current_section.children.push(child_section);
-                continue;
+        if let Some(m) = title_re.find(line)
+            && m.start() == 0
+        {
+            let heading_scope = m.end();
+            
+            // If we're at a higher or equal level, go up a level
+            if heading_scope <= current_scope {
+                return current_section;
             }
+            
+            let section_name = line[heading_scope..].trim();
+            *line_num += 1;
+            
+            // Create a new section and recursively parse its contents
+            let child_section = get_binds_recursive(
+                lines,
+                line_num,
+                heading_scope,
+                Section::new(section_name)
+            );
+            
+            current_section.children.push(child_section);
+            continue;
         }
         
         // Parse keybinds
@@ -174,10 +174,10 @@ fn get_binds_recursive(
             if let Some(keybind) = get_keybind_at_line(lines, *line_num, COMMENT_BIND_PATTERN.len()) {
                 current_section.keybinds.push(keybind);
             }
-        } else if line.trim().starts_with("bind") {
-            if let Some(keybind) = get_keybind_at_line(lines, *line_num, 0) {
-                current_section.keybinds.push(keybind);
-            }
+        } else if line.trim().starts_with("bind")
+            && let Some(keybind) = get_keybind_at_line(lines, *line_num, 0)
+        {
+            current_section.keybinds.push(keybind);
         }
         
         *line_num += 1;
@@ -1,11 +1,102 @@
-use anyhow::{Context, Result, bail};
+use anyhow::{Result, anyhow, bail};
 use clap::{Args, Subcommand};
-use serde::Deserialize;
-use std::process::Command;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{self, Write};
 
-#[derive(Deserialize, Debug)]
-struct HyprlandWorkspace {
-    id: i32,
+use crate::config;
+use crate::hyprland_ipc::{EventStream, HyprlandIpc};
+
+const DEFAULT_GROUP_SIZE: i32 = 10;
+
+/// The `workspaces` section of config.jsonc.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct WorkspacesConfig {
+    /// Workspaces per group, when it should differ from `shown`.
+    group_size: Option<i32>,
+    /// How many workspaces the bar shows; the group size unless `groupSize` is set.
+    shown: Option<i32>,
+    #[serde(default)]
+    per_monitor: bool,
+}
+
+/// How workspace IDs are split into groups, resolved from flags and config.
+#[derive(Debug, Clone, Copy)]
+struct WorkspaceGroups {
+    size: i32,
+    per_monitor: bool,
+}
+
+impl WorkspaceGroups {
+    fn resolve(args: &GroupArgs) -> Result<Self> {
+        Self::from_config(args, config::cached_section("workspaces")?)
+    }
+
+    fn from_config(args: &GroupArgs, config: WorkspacesConfig) -> Result<Self> {
+        let size = args.group_size
+            .or(config.group_size)
+            .or(config.shown)
+            .unwrap_or(DEFAULT_GROUP_SIZE);
+        if size < 1 {
+            bail!("Workspace group size must be at least 1, got {}", size);
+        }
+        Ok(WorkspaceGroups {
+            size,
+            per_monitor: args.per_monitor || config.per_monitor,
+        })
+    }
+
+    /// The 1-based slot of a workspace inside its group.
+    fn slot(&self, id: i32) -> i32 {
+        // Named and special workspaces have IDs below 1; treat them as the first slot.
+        (id.max(1) - 1) % self.size + 1
+    }
+
+    /// The ID just below the first workspace of group `index` (0-based).
+    fn group_base(&self, index: i32) -> i32 {
+        index * self.size
+    }
+
+    /// The ID just below the first workspace of the group holding `id`.
+    fn base_of(&self, id: i32) -> i32 {
+        id.max(1) - self.slot(id)
+    }
+
+    /// The ID just below the first workspace of the current group.
+    fn current_base(&self, ipc: &HyprlandIpc, current_id: i32) -> Result<i32> {
+        if self.per_monitor {
+            let mut monitors = ipc.monitors()?;
+            monitors.sort_by_key(|m| m.id);
+            let index = monitors
+                .iter()
+                .position(|m| m.focused)
+                .ok_or_else(|| anyhow!("No focused monitor found"))?;
+            return Ok(self.group_base(index as i32));
+        }
+        Ok(self.base_of(current_id))
+    }
+
+    /// Workspace `relative_id` (1-based) of the group starting after `base`.
+    fn target(&self, base: i32, relative_id: i32) -> Result<i32> {
+        if relative_id < 1 || relative_id > self.size {
+            bail!("Workspace ID must be between 1 and {}, got {}", self.size, relative_id);
+        }
+        Ok(base + relative_id)
+    }
+
+    /// The first workspace `step` slots at a time away from `slot`, wrapping
+    /// inside the group, that `accept` takes.
+    fn step_within(&self, base: i32, slot: i32, step: i32, accept: impl Fn(i32) -> bool) -> Option<i32> {
+        (1..=self.size)
+            .map(|offset| base + (slot - 1 + step * offset).rem_euclid(self.size) + 1)
+            .find(|&candidate| accept(candidate))
+    }
+
+    /// The same slot `step` groups away, stopping at the first group.
+    fn paged(&self, base: i32, slot: i32, step: i32) -> i32 {
+        (base + step * self.size).max(0) + slot
+    }
 }
 
 #[derive(Debug, Subcommand)]
@@ -14,6 +105,40 @@ pub enum HyprlandCommands {
     Workspace(WorkspaceArgs),
     /// Move active window to a workspace in the current group silently.
     MoveToWorkspace(SilentMoveArgs),
+    /// Switch to the next workspace, wrapping around inside the current group.
+    Next(RelativeArgs),
+    /// Switch to the previous workspace, wrapping around inside the current group.
+    Prev(RelativeArgs),
+    /// Switch to the same slot in the next workspace group.
+    NextGroup(GroupPageArgs),
+    /// Switch to the same slot in the previous workspace group.
+    PrevGroup(GroupPageArgs),
+    /// Move the active window to the same slot in another workspace group.
+    MoveToGroup(MoveToGroupArgs),
+    /// Stream Hyprland events as newline-delimited JSON.
+    Events(EventsArgs),
+    /// Window management helpers.
+    #[command(subcommand)]
+    Window(WindowCommands),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WindowCommands {
+    /// Swap the active window with the master window.
+    SwapMaster,
+    /// Focus the previously focused window (alt-tab).
+    FocusLast,
+    /// Toggle a named scratchpad, launching its app on first use.
+    Scratch(ScratchArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ScratchArgs {
+    /// Name of the scratchpad, used as the special workspace name.
+    pub name: String,
+    /// Command that launches the scratchpad app.
+    #[clap(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    pub command: Vec<String>,
 }
 
 #[derive(Debug, Args)]
@@ -23,58 +148,342 @@ pub struct WorkspaceArgs {
     /// Move the active window along with switching the workspace.
     #[clap(long)]
     pub move_window: bool,
+    #[command(flatten)]
+    pub group: GroupArgs,
 }
 
 #[derive(Debug, Args)]
 pub struct SilentMoveArgs {
     /// The workspace ID (e.g., 1-10) to move the window to.
     pub id: i32,
+    #[command(flatten)]
+    pub group: GroupArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct GroupArgs {
+    /// Number of workspaces per group [default: config.jsonc workspaces.groupSize or workspaces.shown, or 10]
+    #[clap(long)]
+    pub group_size: Option<i32>,
+    /// Bind each workspace group to a monitor, so group N lives on the Nth monitor.
+    #[clap(long)]
+    pub per_monitor: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RelativeArgs {
+    /// Skip workspaces that have no windows.
+    #[clap(long)]
+    pub skip_empty: bool,
+    /// Move the active window along with switching the workspace.
+    #[clap(long)]
+    pub move_window: bool,
+    #[command(flatten)]
+    pub group: GroupArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct GroupPageArgs {
+    /// Move the active window along with switching the workspace.
+    #[clap(long)]
+    pub move_window: bool,
+    #[command(flatten)]
+    pub group: GroupArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct MoveToGroupArgs {
+    /// The group number, starting at 1.
+    pub number: i32,
+    /// Switch to the target workspace as well.
+    #[clap(long)]
+    pub follow: bool,
+    #[command(flatten)]
+    pub group: GroupArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct EventsArgs {
+    /// Only print these events (e.g. workspace,activewindow).
+    #[clap(long, value_delimiter = ',')]
+    pub only: Vec<String>,
+}
+
+fn get_target_workspace_id(ipc: &HyprlandIpc, groups: WorkspaceGroups, relative_id: i32) -> Result<i32> {
+    // Validate before asking Hyprland anything.
+    groups.target(0, relative_id)?;
+    let current_id = ipc.active_workspace()?.id;
+    groups.target(groups.current_base(ipc, current_id)?, relative_id)
 }
 
-fn get_target_workspace_id(relative_id: i32) -> Result<i32> {
-    let output = Command::new("hyprctl")
-        .args(["-j", "activeworkspace"])
-        .output()
-        .context("Failed to execute hyprctl")?;
+/// Steps `step` slots away from the current workspace, wrapping inside the
+/// current group. With `skip_empty`, workspaces without windows are passed over;
+/// if none qualify the current workspace is kept.
+fn get_relative_workspace_id(ipc: &HyprlandIpc, groups: WorkspaceGroups, step: i32, skip_empty: bool) -> Result<i32> {
+    let current_id = ipc.active_workspace()?.id;
+    let base = groups.current_base(ipc, current_id)?;
+    let slot = groups.slot(current_id);
+
+    let occupied: HashSet<i32> = if skip_empty {
+        ipc.workspaces()?
+            .into_iter()
+            .filter(|w| w.windows > 0)
+            .map(|w| w.id)
+            .collect()
+    } else {
+        HashSet::new()
+    };
+
+    Ok(groups
+        .step_within(base, slot, step, |candidate| !skip_empty || occupied.contains(&candidate))
+        .unwrap_or(current_id))
+}
 
-    if !output.status.success() {
-        bail!("Failed to get active workspace from hyprctl");
+/// The workspace in the same slot `step` groups away, stopping at the first group.
+fn get_paged_workspace_id(ipc: &HyprlandIpc, groups: WorkspaceGroups, step: i32) -> Result<i32> {
+    if groups.per_monitor {
+        bail!("Paging between groups is not available with per-monitor groups");
     }
+    let current_id = ipc.active_workspace()?.id;
+    let base = groups.current_base(ipc, current_id)?;
+    Ok(groups.paged(base, groups.slot(current_id), step))
+}
 
-    let current_workspace: HyprlandWorkspace = serde_json::from_slice(&output.stdout)
-        .context("Failed to parse hyprctl output")?;
+/// Moves the active window to the same slot in group `number`. With
+/// per-monitor groups the workspace is also placed on the group's monitor.
+fn move_to_group(ipc: &HyprlandIpc, groups: WorkspaceGroups, args: &MoveToGroupArgs) -> Result<()> {
+    if args.number < 1 {
+        bail!("Group number must be at least 1, got {}", args.number);
+    }
+    let monitor = if groups.per_monitor {
+        let mut monitors = ipc.monitors()?;
+        monitors.sort_by_key(|m| m.id);
+        let count = monitors.len();
+        let monitor = monitors
+            .into_iter()
+            .nth(args.number as usize - 1)
+            .ok_or_else(|| anyhow!("Group {} has no monitor, only {} connected", args.number, count))?;
+        Some(monitor)
+    } else {
+        None
+    };
 
-    let current_id = current_workspace.id;
-    let group_base = ((current_id - 1) / 10) * 10;
-    Ok(group_base + relative_id)
+    let current_id = ipc.active_workspace()?.id;
+    let target_id = groups.group_base(args.number - 1) + groups.slot(current_id);
+    let action = if args.follow { "movetoworkspace" } else { "movetoworkspacesilent" };
+    dispatch_to_workspace(ipc, action, target_id)?;
+    if let Some(monitor) = monitor {
+        ipc.dispatch("moveworkspacetomonitor", &format!("{} {}", target_id, monitor.name))?;
+    }
+    Ok(())
+}
+
+fn dispatch_to_workspace(ipc: &HyprlandIpc, action: &str, target_id: i32) -> Result<()> {
+    ipc.dispatch(action, &target_id.to_string())
+}
+
+/// With per-monitor groups the workspace is pulled onto the focused monitor,
+/// since `workspace N` would follow it to wherever it was opened before.
+fn switch_to_workspace(ipc: &HyprlandIpc, groups: WorkspaceGroups, target_id: i32, move_window: bool) -> Result<()> {
+    if !groups.per_monitor {
+        if move_window {
+            dispatch_to_workspace(ipc, "movetoworkspace", target_id)?;
+        }
+        return dispatch_to_workspace(ipc, "workspace", target_id);
+    }
+    if move_window {
+        dispatch_to_workspace(ipc, "movetoworkspacesilent", target_id)?;
+    }
+    dispatch_to_workspace(ipc, "focusworkspaceoncurrentmonitor", target_id)
 }
 
-fn dispatch_hyprctl(action: &str, target_id: i32) -> Result<()> {
-    let status = Command::new("hyprctl")
-        .arg("dispatch")
-        .arg(action)
-        .arg(target_id.to_string())
-        .status()
-        .context("Failed to execute hyprctl dispatch")?;
+/// Focuses the window that had focus before the current one. Hyprland
+/// tracks focus order itself (`focusHistoryID`), so no state is kept here.
+fn focus_last_window(ipc: &HyprlandIpc) -> Result<()> {
+    let previous = ipc.clients()?
+        .into_iter()
+        .filter(|c| c.focus_history_id > 0 && c.mapped && !c.hidden)
+        .min_by_key(|c| c.focus_history_id)
+        .ok_or_else(|| anyhow!("No previously focused window"))?;
+    ipc.dispatch("focuswindow", &format!("address:{}", previous.address))
+}
+
+/// Quotes an argument for the `sh -c` that Hyprland's `exec` runs.
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if plain {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Shows or hides `special:<name>`, launching the app into it when the
+/// scratchpad has no windows yet.
+fn toggle_scratchpad(ipc: &HyprlandIpc, args: &ScratchArgs) -> Result<()> {
+    let workspace = format!("special:{}", args.name);
+    let exists = ipc.clients()?.iter().any(|c| c.workspace.name == workspace);
+    if exists {
+        return ipc.dispatch("togglespecialworkspace", &args.name);
+    }
+
+    let command: Vec<String> = args.command.iter().map(|arg| shell_quote(arg)).collect();
+    ipc.dispatch("exec", &format!("[workspace {}] {}", workspace, command.join(" ")))?;
+    // The rule only places the window; show the scratchpad so it opens in view.
+    let shown = ipc.focused_monitor()?.special_workspace.name == workspace;
+    if !shown {
+        ipc.dispatch("togglespecialworkspace", &args.name)?;
+    }
+    Ok(())
+}
+
+fn handle_window_command(ipc: &HyprlandIpc, command: &WindowCommands) -> Result<()> {
+    match command {
+        WindowCommands::SwapMaster => ipc.dispatch("layoutmsg", "swapwithmaster"),
+        WindowCommands::FocusLast => focus_last_window(ipc),
+        WindowCommands::Scratch(args) => toggle_scratchpad(ipc, args),
+    }
+}
 
-    if !status.success() {
-        bail!("hyprctl dispatch failed with status: {}", status);
+fn stream_events(args: &EventsArgs) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    for event in EventStream::connect()? {
+        let event = event?;
+        if !args.only.is_empty() && !args.only.iter().any(|name| name == event.name()) {
+            continue;
+        }
+        let line = serde_json::to_string(&event)?;
+        // Stop quietly once the reader goes away.
+        if writeln!(stdout, "{}", line).and_then(|_| stdout.flush()).is_err() {
+            break;
+        }
     }
     Ok(())
 }
 
 pub fn handle_hyprland_command(command: &HyprlandCommands, _debug: bool) -> Result<()> {
+    let ipc = HyprlandIpc::from_env()?;
     match command {
         HyprlandCommands::Workspace(args) => {
-            let target_id = get_target_workspace_id(args.id)?;
-            if args.move_window {
-                dispatch_hyprctl("movetoworkspace", target_id)?;
-            }
-            dispatch_hyprctl("workspace", target_id)
+            let groups = WorkspaceGroups::resolve(&args.group)?;
+            let target_id = get_target_workspace_id(&ipc, groups, args.id)?;
+            switch_to_workspace(&ipc, groups, target_id, args.move_window)
         }
         HyprlandCommands::MoveToWorkspace(args) => {
-            let target_id = get_target_workspace_id(args.id)?;
-            dispatch_hyprctl("movetoworkspacesilent", target_id)
+            let groups = WorkspaceGroups::resolve(&args.group)?;
+            let target_id = get_target_workspace_id(&ipc, groups, args.id)?;
+            dispatch_to_workspace(&ipc, "movetoworkspacesilent", target_id)
+        }
+        HyprlandCommands::Next(args) | HyprlandCommands::Prev(args) => {
+            let step = if matches!(command, HyprlandCommands::Next(_)) { 1 } else { -1 };
+            let groups = WorkspaceGroups::resolve(&args.group)?;
+            let target_id = get_relative_workspace_id(&ipc, groups, step, args.skip_empty)?;
+            switch_to_workspace(&ipc, groups, target_id, args.move_window)
+        }
+        HyprlandCommands::NextGroup(args) | HyprlandCommands::PrevGroup(args) => {
+            let step = if matches!(command, HyprlandCommands::NextGroup(_)) { 1 } else { -1 };
+            let groups = WorkspaceGroups::resolve(&args.group)?;
+            let target_id = get_paged_workspace_id(&ipc, groups, step)?;
+            switch_to_workspace(&ipc, groups, target_id, args.move_window)
         }
+        HyprlandCommands::MoveToGroup(args) => {
+            let groups = WorkspaceGroups::resolve(&args.group)?;
+            move_to_group(&ipc, groups, args)
+        }
+        HyprlandCommands::Events(args) => stream_events(args),
+        HyprlandCommands::Window(command) => handle_window_command(&ipc, command),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group_args(group_size: Option<i32>, per_monitor: bool) -> GroupArgs {
+        GroupArgs { group_size, per_monitor }
+    }
+
+    fn workspaces(group_size: Option<i32>, shown: Option<i32>) -> WorkspacesConfig {
+        WorkspacesConfig { group_size, shown, per_monitor: false }
+    }
+
+    #[test]
+    fn group_size_prefers_flag_then_group_size_then_shown() {
+        let size = |args, config| WorkspaceGroups::from_config(&args, config).unwrap().size;
+        assert_eq!(size(group_args(Some(4), false), workspaces(Some(6), Some(8))), 4);
+        assert_eq!(size(group_args(None, false), workspaces(Some(6), Some(8))), 6);
+        assert_eq!(size(group_args(None, false), workspaces(None, Some(8))), 8);
+        assert_eq!(size(group_args(None, false), workspaces(None, None)), DEFAULT_GROUP_SIZE);
+        assert!(WorkspaceGroups::from_config(&group_args(Some(0), false), workspaces(None, None)).is_err());
+    }
+
+    fn groups(size: i32) -> WorkspaceGroups {
+        WorkspaceGroups { size, per_monitor: false }
+    }
+
+    #[test]
+    fn slots_and_bases() {
+        let groups = groups(10);
+        assert_eq!((groups.slot(1), groups.base_of(1)), (1, 0));
+        assert_eq!((groups.slot(10), groups.base_of(10)), (10, 0));
+        assert_eq!((groups.slot(11), groups.base_of(11)), (1, 10));
+        assert_eq!((groups.slot(27), groups.base_of(27)), (7, 20));
+        // Named and special workspaces count as the first slot of the first group.
+        assert_eq!((groups.slot(-98), groups.base_of(-98)), (1, 0));
+
+        let groups = WorkspaceGroups { size: 8, per_monitor: false };
+        assert_eq!((groups.slot(9), groups.base_of(9)), (1, 8));
+        assert_eq!(groups.group_base(2), 16);
+    }
+
+    #[test]
+    fn target_stays_inside_the_group() {
+        let groups = groups(10);
+        assert_eq!(groups.target(10, 3).unwrap(), 13);
+        assert_eq!(groups.target(0, 10).unwrap(), 10);
+        assert!(groups.target(0, 0).is_err());
+        assert!(groups.target(0, 11).is_err());
+    }
+
+    #[test]
+    fn stepping_wraps_inside_the_group() {
+        let groups = groups(5);
+        let any = |_| true;
+        assert_eq!(groups.step_within(5, 2, 1, any), Some(8));
+        assert_eq!(groups.step_within(5, 5, 1, any), Some(6));
+        assert_eq!(groups.step_within(5, 1, -1, any), Some(10));
+
+        let occupied = [7, 9];
+        let occupied_only = |id| occupied.contains(&id);
+        assert_eq!(groups.step_within(5, 2, 1, occupied_only), Some(9));
+        assert_eq!(groups.step_within(5, 4, 1, occupied_only), Some(7));
+        assert_eq!(groups.step_within(5, 2, -1, occupied_only), Some(9));
+        assert_eq!(groups.step_within(0, 1, 1, occupied_only), None);
+    }
+
+    #[test]
+    fn paging_keeps_the_slot_and_stops_at_the_first_group() {
+        let groups = groups(10);
+        assert_eq!(groups.paged(0, 3, 1), 13);
+        assert_eq!(groups.paged(10, 3, -1), 3);
+        assert_eq!(groups.paged(0, 3, -1), 3);
+        assert_eq!(groups.paged(20, 10, 2), 50);
+    }
+
+    #[test]
+    fn shell_quote_keeps_arguments_whole() {
+        assert_eq!(shell_quote("kitty"), "kitty");
+        assert_eq!(shell_quote("--class=scratch"), "--class=scratch");
+        assert_eq!(shell_quote("my notes.md"), "'my notes.md'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote("$HOME;rm"), "'$HOME;rm'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn per_monitor_comes_from_flag_or_config() {
+        let config = WorkspacesConfig { per_monitor: true, ..workspaces(None, None) };
+        assert!(WorkspaceGroups::from_config(&group_args(None, false), config).unwrap().per_monitor);
+        assert!(WorkspaceGroups::from_config(&group_args(None, true), workspaces(None, None)).unwrap().per_monitor);
     }
 }
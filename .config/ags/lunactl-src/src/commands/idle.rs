@@ -0,0 +1,399 @@
+use anyhow::{Context, Result, bail};
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::config;
+use crate::hyprland_ipc::HyprlandIpc;
+
+#[derive(Debug, Subcommand)]
+pub enum IdleCommands {
+    /// Show the hypridle timeouts, or write them from config.jsonc `idle.*`.
+    Config(IdleConfigArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct IdleConfigArgs {
+    /// Write the timeouts to hypridle.conf and restart hypridle.
+    #[clap(long)]
+    pub apply: bool,
+    /// Seconds until the screen dims (saved to idle.dim, implies --apply).
+    #[clap(long)]
+    pub dim: Option<u32>,
+    /// Seconds until the session locks (saved to idle.lock, implies --apply).
+    #[clap(long)]
+    pub lock: Option<u32>,
+    /// Seconds until the screen turns off (saved to idle.screenOff, implies --apply).
+    #[clap(long)]
+    pub screen_off: Option<u32>,
+    /// Seconds until the system suspends (saved to idle.suspend, implies --apply).
+    #[clap(long)]
+    pub suspend: Option<u32>,
+    /// Leave hypridle running with its old timeouts.
+    #[clap(long)]
+    pub no_restart: bool,
+    /// Print the timeouts as JSON.
+    #[clap(long)]
+    pub json: bool,
+    /// hypridle config to use [default: ~/.config/hypr/hypridle.conf]
+    #[clap(long)]
+    pub file: Option<PathBuf>,
+}
+
+impl IdleConfigArgs {
+    fn overrides(&self) -> Timeouts {
+        Timeouts {
+            dim: self.dim,
+            lock: self.lock,
+            screen_off: self.screen_off,
+            suspend: self.suspend,
+        }
+    }
+}
+
+/// Timeouts in seconds, in the order they fire, as in config.jsonc `idle.*`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+struct Timeouts {
+    dim: Option<u32>,
+    lock: Option<u32>,
+    screen_off: Option<u32>,
+    suspend: Option<u32>,
+}
+
+impl Timeouts {
+    fn get(&self, stage: Stage) -> Option<u32> {
+        match stage {
+            Stage::Dim => self.dim,
+            Stage::Lock => self.lock,
+            Stage::ScreenOff => self.screen_off,
+            Stage::Suspend => self.suspend,
+        }
+    }
+
+    fn set(&mut self, stage: Stage, value: u32) {
+        match stage {
+            Stage::Dim => self.dim = Some(value),
+            Stage::Lock => self.lock = Some(value),
+            Stage::ScreenOff => self.screen_off = Some(value),
+            Stage::Suspend => self.suspend = Some(value),
+        }
+    }
+
+    /// Fills unset values from `other`.
+    fn or(self, other: Timeouts) -> Timeouts {
+        Timeouts {
+            dim: self.dim.or(other.dim),
+            lock: self.lock.or(other.lock),
+            screen_off: self.screen_off.or(other.screen_off),
+            suspend: self.suspend.or(other.suspend),
+        }
+    }
+
+    /// Each stage has to fire after the ones before it.
+    fn validate(&self) -> Result<()> {
+        let mut previous: Option<(Stage, u32)> = None;
+        for stage in Stage::ALL {
+            let Some(value) = self.get(stage) else {
+                continue;
+            };
+            if value == 0 {
+                bail!("The {} timeout must be greater than zero", stage.name());
+            }
+            if let Some((before, before_value)) = previous
+                && value <= before_value
+            {
+                bail!(
+                    "The {} timeout ({}s) must be later than the {} timeout ({}s)",
+                    stage.name(), value, before.name(), before_value
+                );
+            }
+            previous = Some((stage, value));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stage {
+    Dim,
+    Lock,
+    ScreenOff,
+    Suspend,
+}
+
+impl Stage {
+    const ALL: [Stage; 4] = [Stage::Dim, Stage::Lock, Stage::ScreenOff, Stage::Suspend];
+
+    fn name(self) -> &'static str {
+        match self {
+            Stage::Dim => "dim",
+            Stage::Lock => "lock",
+            Stage::ScreenOff => "screenOff",
+            Stage::Suspend => "suspend",
+        }
+    }
+
+    /// Recognizes a listener by its `on-timeout` command.
+    fn from_command(command: &str) -> Option<Stage> {
+        if command.contains("brightnessctl") {
+            Some(Stage::Dim)
+        } else if command.contains("lock-session") || command.contains("hyprlock") {
+            Some(Stage::Lock)
+        } else if command.contains("dpms off") {
+            Some(Stage::ScreenOff)
+        } else if command.contains("suspend") || command.contains("hibernate") {
+            Some(Stage::Suspend)
+        } else {
+            None
+        }
+    }
+}
+
+/// A `listener { ... }` block in hypridle.conf.
+#[derive(Debug)]
+struct Listener {
+    stage: Option<Stage>,
+    timeout: Option<u32>,
+    /// Index of the `timeout = ...` line.
+    timeout_line: Option<usize>,
+}
+
+fn parse_listeners(lines: &[String]) -> Vec<Listener> {
+    let mut listeners = Vec::new();
+    let mut current: Option<Listener> = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        let code = line.split('#').next().unwrap_or_default().trim();
+        if code.starts_with("listener") && code.ends_with('{') {
+            current = Some(Listener { stage: None, timeout: None, timeout_line: None });
+            continue;
+        }
+        let Some(listener) = current.as_mut() else {
+            continue;
+        };
+        if code == "}" {
+            listeners.extend(current.take());
+            continue;
+        }
+        let Some((key, value)) = code.split_once('=') else {
+            continue;
+        };
+        match key.trim() {
+            "timeout" => {
+                listener.timeout = value.trim().parse().ok();
+                listener.timeout_line = Some(index);
+            }
+            "on-timeout" if listener.stage.is_none() => {
+                listener.stage = Stage::from_command(value);
+            }
+            _ => {}
+        }
+    }
+    listeners
+}
+
+/// Formats seconds the way the stock hypridle.conf comments them, e.g. `2.5min`.
+fn describe_seconds(secs: u32) -> String {
+    if secs.is_multiple_of(3600) {
+        format!("{}h", secs / 3600)
+    } else if secs.is_multiple_of(30) && secs >= 60 {
+        let minutes = secs as f64 / 60.0;
+        format!("{}min", minutes)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Replaces the value of a `timeout = N   # comment` line, keeping its layout.
+fn rewrite_timeout_line(line: &str, value: u32) -> String {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let code = line.split('#').next().unwrap_or_default();
+    let new_code = format!("{}timeout = {}", indent, value);
+    if !line.contains('#') {
+        return new_code;
+    }
+    let padding = code.len().saturating_sub(new_code.len()).max(1);
+    format!("{}{}# {}", new_code, " ".repeat(padding), describe_seconds(value))
+}
+
+fn restart_hypridle() -> Result<()> {
+    let managed_by_systemd = Command::new("systemctl")
+        .args(["--user", "is-active", "--quiet", "hypridle"])
+        .status()
+        .is_ok_and(|status| status.success());
+    if managed_by_systemd {
+        let status = Command::new("systemctl")
+            .args(["--user", "restart", "hypridle"])
+            .status()
+            .context("Failed to execute systemctl")?;
+        if !status.success() {
+            bail!("Failed to restart hypridle.service");
+        }
+        return Ok(());
+    }
+
+    let _ = Command::new("pkill").args(["-x", "hypridle"]).status();
+    // Started through Hyprland so it is not tied to this process.
+    HyprlandIpc::from_env()?.dispatch("exec", "hypridle")
+}
+
+fn handle_config(args: &IdleConfigArgs) -> Result<()> {
+    let path = args.file.clone().unwrap_or_else(|| config::hypr_config_dir().join("hypridle.conf"));
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let listeners = parse_listeners(&lines);
+
+    let mut current = Timeouts::default();
+    for listener in &listeners {
+        if let (Some(stage), Some(timeout)) = (listener.stage, listener.timeout) {
+            current.set(stage, timeout);
+        }
+    }
+
+    let overrides = args.overrides();
+    let apply = args.apply || Stage::ALL.iter().any(|&s| overrides.get(s).is_some());
+    if !apply {
+        let configured = config::section::<Timeouts>("idle")?;
+        if args.json {
+            println!("{}", serde_json::json!({ "hypridle": current, "config": configured }));
+            return Ok(());
+        }
+        println!("{:<10} {:>10} {:>12}", "stage", "hypridle", "config.jsonc");
+        for stage in Stage::ALL {
+            let show = |value: Option<u32>| value.map_or("-".to_string(), |v| format!("{}s", v));
+            println!("{:<10} {:>10} {:>12}", stage.name(), show(current.get(stage)), show(configured.get(stage)));
+        }
+        return Ok(());
+    }
+
+    let wanted = overrides.or(config::section::<Timeouts>("idle")?);
+    let result = wanted.or(current);
+    result.validate()?;
+
+    for stage in Stage::ALL {
+        let Some(value) = wanted.get(stage) else {
+            continue;
+        };
+        let line = listeners.iter()
+            .find(|l| l.stage == Some(stage))
+            .and_then(|l| l.timeout_line)
+            .with_context(|| format!("{} has no {} listener with a timeout", path.display(), stage.name()))?;
+        lines[line] = rewrite_timeout_line(&lines[line], value);
+    }
+
+    let mut output = lines.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+    if output != content {
+        fs::write(&path, output)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    // Keep config.jsonc in step so the next --apply does not revert the
+    // overrides, but only once hypridle.conf has them too
+    let saved: Vec<_> = Stage::ALL.iter()
+        .filter_map(|&stage| overrides.get(stage).map(|value| (stage.name(), value)))
+        .collect();
+    if !saved.is_empty() {
+        config::set_numbers("idle", &saved)?;
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string(&result)?);
+    } else {
+        println!("Wrote {}", path.display());
+    }
+
+    if !args.no_restart {
+        restart_hypridle()?;
+    }
+    Ok(())
+}
+
+pub fn handle_idle_command(command: &IdleCommands, _debug: bool) -> Result<()> {
+    match command {
+        IdleCommands::Config(args) => handle_config(args),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HYPRIDLE: &str = "general {
+    lock_cmd = pidof hyprlock || hyprlock
+}
+
+listener {
+    timeout = 150                                # 2.5min.
+    on-timeout = brightnessctl -s set 10         # set monitor backlight to minimum
+    on-resume = brightnessctl -r                 # monitor backlight restore.
+}
+
+listener {
+    timeout = 300
+    on-timeout = loginctl lock-session
+}
+
+# listener {
+#     timeout = 1
+# }
+
+listener {
+    on-timeout = hyprctl dispatch dpms off
+}
+
+listener {
+    timeout = 1800
+    on-timeout = notify-send hello
+}
+";
+
+    fn lines(content: &str) -> Vec<String> {
+        content.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parse_listeners_finds_stages_and_timeouts() {
+        let listeners = parse_listeners(&lines(HYPRIDLE));
+        let found: Vec<_> = listeners.iter().map(|l| (l.stage, l.timeout, l.timeout_line)).collect();
+        assert_eq!(found, [
+            (Some(Stage::Dim), Some(150), Some(5)),
+            (Some(Stage::Lock), Some(300), Some(11)),
+            (Some(Stage::ScreenOff), None, None),
+            (None, Some(1800), Some(24)),
+        ]);
+    }
+
+    fn timeouts(dim: Option<u32>, lock: Option<u32>, screen_off: Option<u32>, suspend: Option<u32>) -> Timeouts {
+        Timeouts { dim, lock, screen_off, suspend }
+    }
+
+    #[test]
+    fn validate_requires_increasing_timeouts() {
+        assert!(timeouts(Some(150), Some(300), Some(330), Some(1800)).validate().is_ok());
+        assert!(timeouts(None, Some(300), None, Some(1800)).validate().is_ok());
+        assert!(Timeouts::default().validate().is_ok());
+
+        let error = timeouts(Some(300), Some(300), None, None).validate().unwrap_err();
+        assert_eq!(error.to_string(), "The lock timeout (300s) must be later than the dim timeout (300s)");
+        assert!(timeouts(Some(150), None, Some(600), Some(400)).validate().is_err());
+        assert!(timeouts(Some(0), None, None, None).validate().is_err());
+    }
+
+    #[test]
+    fn rewrite_timeout_line_keeps_the_layout() {
+        assert_eq!(
+            rewrite_timeout_line("    timeout = 150                                # 2.5min.", 90),
+            "    timeout = 90                                 # 1.5min"
+        );
+        assert_eq!(rewrite_timeout_line("    timeout = 300", 600), "    timeout = 600");
+        assert_eq!(rewrite_timeout_line("\ttimeout = 1 # s", 7200), "\ttimeout = 7200 # 2h");
+        assert_eq!(rewrite_timeout_line("timeout = 45 # soon", 45), "timeout = 45 # 45s");
+    }
+}
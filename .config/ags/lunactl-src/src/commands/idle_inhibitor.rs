@@ -1,18 +1,191 @@
-use anyhow::{Context, Result, anyhow};
-use clap::Args;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Days, Local, NaiveTime, TimeZone};
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, TryRecvError};
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use wayland_client::protocol::{wl_compositor, wl_registry, wl_surface};
-use wayland_client::{Connection, Dispatch, QueueHandle};
+use wayland_client::{Connection, Dispatch, EventQueue, QueueHandle};
 
 use wayland_protocols::wp::idle_inhibit::zv1::client::{
     zwp_idle_inhibit_manager_v1,
     zwp_idle_inhibitor_v1,
 };
 
+use crate::config;
+use crate::glob;
+use crate::hyprland_ipc::{Event, EventStream, HyprlandIpc};
+use crate::state;
+
+/// How often the release conditions are checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Without a subcommand the inhibitor runs in the foreground until released.
 #[derive(Args, Debug)]
-pub struct IdleInhibitorArgs;
+#[command(args_conflicts_with_subcommands = true)]
+pub struct IdleInhibitorArgs {
+    #[command(subcommand)]
+    pub command: Option<IdleInhibitorCommand>,
+    #[command(flatten)]
+    pub release: ReleaseArgs,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IdleInhibitorCommand {
+    /// Starts the inhibitor in the background.
+    On(ReleaseArgs),
+    /// Stops the background inhibitor.
+    Off,
+    /// Starts the inhibitor if it is off, stops it (automatic mode included) otherwise.
+    Toggle(ReleaseArgs),
+    /// Inhibits idle automatically while a fullscreen window is visible, audio
+    /// is playing or a window class from config.jsonc idle.inhibitClasses is focused.
+    Auto,
+    /// Shows whether the inhibitor is running, why, and until when.
+    Status {
+        /// Print the status as JSON.
+        #[clap(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ReleaseArgs {
+    /// Release after this long, e.g. 30m, 1h30m or 90s (a bare number is minutes).
+    #[clap(long = "for", value_name = "DURATION", value_parser = parse_duration)]
+    pub duration: Option<Duration>,
+    /// Release at this time of day (HH:MM), tomorrow if it has already passed.
+    #[clap(long, value_name = "HH:MM", conflicts_with = "duration")]
+    pub until: Option<String>,
+    /// Release once the process with this PID exits.
+    #[clap(long, value_name = "PID")]
+    pub while_pid: Option<u32>,
+    /// Release once no process with this name is running.
+    #[clap(long, value_name = "NAME")]
+    pub while_process: Option<String>,
+}
+
+impl ReleaseArgs {
+    /// The arguments that recreate these conditions in a background process.
+    fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(duration) = self.duration {
+            args.extend(["--for".to_string(), format!("{}s", duration.as_secs())]);
+        }
+        if let Some(until) = &self.until {
+            args.extend(["--until".to_string(), until.clone()]);
+        }
+        if let Some(pid) = self.while_pid {
+            args.extend(["--while-pid".to_string(), pid.to_string()]);
+        }
+        if let Some(name) = &self.while_process {
+            args.extend(["--while-process".to_string(), name.clone()]);
+        }
+        args
+    }
+}
+
+/// Reported by `idle-inhibitor status --json`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+struct InhibitorStatus {
+    /// Idle is being inhibited right now.
+    active: bool,
+    /// An inhibitor process is running; automatic mode only inhibits on demand.
+    running: bool,
+    /// `manual` or `auto`.
+    mode: Option<String>,
+    pid: Option<u32>,
+    /// `manual`, the conditions that will release it, or what triggered automatic mode.
+    reason: Option<String>,
+    since: Option<DateTime<Local>>,
+    expires: Option<DateTime<Local>>,
+}
+
+/// Conditions that end the inhibition; the first one to trigger wins.
+#[derive(Debug)]
+struct ReleaseConditions {
+    deadline: Option<DateTime<Local>>,
+    pid: Option<u32>,
+    process: Option<String>,
+}
+
+impl ReleaseConditions {
+    fn from_args(args: &ReleaseArgs) -> Result<Self> {
+        let deadline = match (&args.duration, &args.until) {
+            (Some(duration), _) => {
+                let duration = chrono::Duration::from_std(*duration)
+                    .map_err(|_| anyhow!("Duration is too long"))?;
+                let deadline = Local::now().checked_add_signed(duration)
+                    .ok_or_else(|| anyhow!("Duration is too long"))?;
+                Some(deadline)
+            }
+            (_, Some(until)) => Some(next_time_of_day(until)?),
+            _ => None,
+        };
+        if let Some(pid) = args.while_pid
+            && !pid_running(pid)
+        {
+            bail!("No process with PID {} is running", pid);
+        }
+        if let Some(name) = &args.while_process
+            && !process_running(name)
+        {
+            bail!("No process named {} is running", name);
+        }
+        Ok(ReleaseConditions {
+            deadline,
+            pid: args.while_pid,
+            process: args.while_process.clone(),
+        })
+    }
+
+    /// Why the inhibitor should be released now, if it should.
+    fn triggered(&self) -> Option<String> {
+        if let Some(deadline) = self.deadline
+            && Local::now() >= deadline
+        {
+            return Some(format!("reached {}", deadline.format("%H:%M")));
+        }
+        if let Some(pid) = self.pid
+            && !pid_running(pid)
+        {
+            return Some(format!("process {} exited", pid));
+        }
+        if let Some(name) = &self.process
+            && !process_running(name)
+        {
+            return Some(format!("{} is no longer running", name));
+        }
+        None
+    }
+
+    fn describe(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(deadline) = self.deadline {
+            parts.push(format!("until {}", deadline.format("%Y-%m-%d %H:%M")));
+        }
+        if let Some(pid) = self.pid {
+            parts.push(format!("while PID {} runs", pid));
+        }
+        if let Some(name) = &self.process {
+            parts.push(format!("while {} runs", name));
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
 
 // The state of our application.
 struct AppState {
@@ -21,60 +194,558 @@ struct AppState {
     inhibitor: Option<zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1>,
 }
 
-pub fn handle_idle_inhibitor_command(_args: &IdleInhibitorArgs, _debug: bool) -> Result<()> {
-    let conn = Connection::connect_to_env()
-        .context("Failed to connect to Wayland display")?;
+// --- Control Socket ---
 
-    let mut event_queue = conn.new_event_queue();
-    let qh = event_queue.handle();
-    let display = conn.display();
-    let _registry = display.get_registry(&qh, ());
+fn socket_path() -> Result<PathBuf> {
+    Ok(state::runtime_dir()?.join("idle-inhibitor.sock"))
+}
 
-    let mut state = AppState {
-        compositor: None,
-        idle_inhibit_manager: None,
-        inhibitor: None,
+/// Sends a request to the running inhibitor. Returns `None` when none is
+/// running, cleaning up a socket left behind by a killed process.
+fn send_request(request: &str) -> Result<Option<String>> {
+    let path = socket_path()?;
+    let mut stream = match UnixStream::connect(&path) {
+        Ok(stream) => stream,
+        Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => {
+            let _ = fs::remove_file(&path);
+            return Ok(None);
+        }
+        Err(e) => return Err(e).with_context(|| format!("Failed to connect to {}", path.display())),
     };
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+    writeln!(stream, "{}", request)?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)
+        .context("Failed to read the idle inhibitor's response")?;
+    Ok(Some(response.trim().to_string()))
+}
+
+fn query_status() -> Result<InhibitorStatus> {
+    match send_request("status")? {
+        Some(response) => serde_json::from_str(&response)
+            .context("Failed to parse the idle inhibitor's status"),
+        None => Ok(InhibitorStatus::default()),
+    }
+}
 
-    // First roundtrip to get the globals from the server
-    event_queue.roundtrip(&mut state)
-        .context("Failed to perform initial Wayland roundtrip")?;
+/// Removes the socket when the inhibitor exits.
+struct ControlSocket {
+    listener: UnixListener,
+    path: PathBuf,
+}
 
-    let compositor = state.compositor.take()
-        .ok_or_else(|| anyhow!("Compositor not found"))?;
-    let idle_inhibit_manager = state.idle_inhibit_manager.take()
-        .ok_or_else(|| anyhow!("Idle inhibit manager not found"))?;
+impl ControlSocket {
+    fn bind() -> Result<Self> {
+        if let Some(response) = send_request("status")? {
+            let status: InhibitorStatus = serde_json::from_str(&response).unwrap_or_default();
+            bail!("Idle inhibitor is already running (PID {})", status.pid.unwrap_or_default());
+        }
+        let path = socket_path()?;
+        let listener = UnixListener::bind(&path)
+            .with_context(|| format!("Failed to bind {}", path.display()))?;
+        listener.set_nonblocking(true)?;
+        Ok(ControlSocket { listener, path })
+    }
 
-    let surface = compositor.create_surface(&qh, ());
-    let inhibitor = idle_inhibit_manager.create_inhibitor(&surface, &qh, ());
-    state.inhibitor = Some(inhibitor);
+    /// Answers pending requests; returns true once asked to turn off.
+    fn serve(&self, status: &InhibitorStatus) -> bool {
+        let mut off = false;
+        while let Ok((stream, _)) = self.listener.accept() {
+            off |= handle_request(stream, status).unwrap_or(false);
+        }
+        off
+    }
+}
 
-    println!("Idle inhibition enabled. Press Ctrl-C to exit.");
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
 
-    // Set up a Ctrl-C handler to allow graceful exit
+fn handle_request(mut stream: UnixStream, status: &InhibitorStatus) -> io::Result<bool> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+    match request.trim() {
+        "status" => {
+            writeln!(stream, "{}", serde_json::to_string(status)?)?;
+            Ok(false)
+        }
+        "off" => {
+            writeln!(stream, "ok")?;
+            Ok(true)
+        }
+        other => {
+            writeln!(stream, "unknown request: {}", other)?;
+            Ok(false)
+        }
+    }
+}
+
+// --- Commands ---
+
+/// Re-runs lunactl detached, so the inhibitor outlives the caller. A
+/// running automatic mode is replaced, since it may not be inhibiting.
+fn turn_on(release: &ReleaseArgs) -> Result<()> {
+    let status = query_status()?;
+    if status.running && status.mode.as_deref() != Some("auto") {
+        println!("Idle inhibitor is already on.");
+        return Ok(());
+    }
+    // Validate here so mistakes are reported to the caller, not lost in the background.
+    ReleaseConditions::from_args(release)?;
+
+    if status.running {
+        send_request("off")?;
+        for _ in 0..30 {
+            if !query_status()?.running {
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    let exe = env::current_exe().context("Failed to locate the lunactl executable")?;
+    Command::new(exe)
+        .arg("idle-inhibitor")
+        .args(release.to_args())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .context("Failed to start the idle inhibitor")?;
+
+    for _ in 0..30 {
+        thread::sleep(Duration::from_millis(100));
+        if query_status()?.running {
+            println!("Idle inhibitor on.");
+            return Ok(());
+        }
+    }
+    bail!("Idle inhibitor did not start")
+}
+
+fn turn_off() -> Result<()> {
+    match send_request("off")? {
+        Some(_) => println!("Idle inhibitor off."),
+        None => println!("Idle inhibitor is not running."),
+    }
+    Ok(())
+}
+
+fn print_status(json: bool) -> Result<()> {
+    let status = query_status()?;
+    if json {
+        println!("{}", serde_json::to_string(&status)?);
+        return Ok(());
+    }
+    if !status.running {
+        println!("Idle inhibitor off");
+        return Ok(());
+    }
+    if status.mode.as_deref() == Some("auto") {
+        match &status.reason {
+            Some(reason) => println!("Idle inhibitor on (auto, {})", reason),
+            None => println!("Idle inhibitor on (auto, idle allowed)"),
+        }
+        return Ok(());
+    }
+    println!("Idle inhibitor on ({})", status.reason.as_deref().unwrap_or("manual"));
+    if let Some(expires) = status.expires {
+        println!("Expires {}", expires.format("%Y-%m-%d %H:%M"));
+    }
+    Ok(())
+}
+
+pub fn handle_idle_inhibitor_command(args: &IdleInhibitorArgs, _debug: bool) -> Result<()> {
+    match &args.command {
+        None => run_inhibitor(&args.release),
+        Some(IdleInhibitorCommand::On(release)) => turn_on(release),
+        Some(IdleInhibitorCommand::Off) => turn_off(),
+        Some(IdleInhibitorCommand::Toggle(release)) => {
+            // Any running inhibitor counts, so an idle automatic mode is switched off rather than replaced
+            if query_status()?.running {
+                turn_off()
+            } else {
+                turn_on(release)
+            }
+        }
+        Some(IdleInhibitorCommand::Auto) => run_auto(),
+        Some(IdleInhibitorCommand::Status { json }) => print_status(*json),
+    }
+}
+
+/// A Wayland connection holding a surface that idle inhibitors attach to.
+struct IdleInhibit {
+    conn: Connection,
+    event_queue: EventQueue<AppState>,
+    state: AppState,
+    manager: zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1,
+    surface: wl_surface::WlSurface,
+}
+
+impl IdleInhibit {
+    fn connect() -> Result<Self> {
+        let conn = Connection::connect_to_env()
+            .context("Failed to connect to Wayland display")?;
+
+        let mut event_queue = conn.new_event_queue();
+        let qh = event_queue.handle();
+        let display = conn.display();
+        let _registry = display.get_registry(&qh, ());
+
+        let mut state = AppState {
+            compositor: None,
+            idle_inhibit_manager: None,
+            inhibitor: None,
+        };
+
+        // First roundtrip to get the globals from the server
+        event_queue.roundtrip(&mut state)
+            .context("Failed to perform initial Wayland roundtrip")?;
+
+        let compositor = state.compositor.take()
+            .ok_or_else(|| anyhow!("Compositor not found"))?;
+        let manager = state.idle_inhibit_manager.take()
+            .ok_or_else(|| anyhow!("Idle inhibit manager not found"))?;
+        let surface = compositor.create_surface(&qh, ());
+
+        Ok(IdleInhibit { conn, event_queue, state, manager, surface })
+    }
+
+    fn is_active(&self) -> bool {
+        self.state.inhibitor.is_some()
+    }
+
+    fn set_active(&mut self, active: bool) -> Result<()> {
+        match (active, self.state.inhibitor.take()) {
+            (true, None) => {
+                let qh = self.event_queue.handle();
+                self.state.inhibitor = Some(self.manager.create_inhibitor(&self.surface, &qh, ()));
+            }
+            (false, Some(inhibitor)) => inhibitor.destroy(),
+            (_, inhibitor) => self.state.inhibitor = inhibitor,
+        }
+        self.conn.flush().context("Failed to flush Wayland connection")
+    }
+
+    fn dispatch(&mut self) -> Result<()> {
+        self.event_queue
+            .dispatch_pending(&mut self.state)
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Sets up a Ctrl-C handler to allow graceful exit.
+fn exit_flag() -> Result<Arc<AtomicBool>> {
     let running = Arc::new(AtomicBool::new(true));
     let r = running.clone();
     ctrlc::set_handler(move || {
         r.store(false, Ordering::SeqCst);
     })
-    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+    .map_err(|e| std::io::Error::other(e.to_string()))?;
+    Ok(running)
+}
+
+fn run_inhibitor(args: &ReleaseArgs) -> Result<()> {
+    let release = ReleaseConditions::from_args(args)?;
+    let socket = ControlSocket::bind()?;
+
+    let mut inhibit = IdleInhibit::connect()?;
+    inhibit.set_active(true)?;
+
+    let status = InhibitorStatus {
+        active: true,
+        running: true,
+        mode: Some("manual".to_string()),
+        pid: Some(process::id()),
+        reason: Some(release.describe().unwrap_or_else(|| "manual".to_string())),
+        since: Some(Local::now()),
+        expires: release.deadline,
+    };
+    match release.describe() {
+        Some(description) => println!("Idle inhibition enabled {}. Press Ctrl-C to exit.", description),
+        None => println!("Idle inhibition enabled. Press Ctrl-C to exit."),
+    }
+
+    let running = exit_flag()?;
 
     // Main event loop
+    let mut last_check = Instant::now();
     while running.load(Ordering::SeqCst) {
-        event_queue
-            .dispatch_pending(&mut state)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
-        std::thread::sleep(std::time::Duration::from_millis(10));
+        inhibit.dispatch()?;
+
+        if socket.serve(&status) {
+            println!("Idle inhibitor turned off.");
+            break;
+        }
+
+        if last_check.elapsed() >= CHECK_INTERVAL {
+            last_check = Instant::now();
+            if let Some(reason) = release.triggered() {
+                println!("Releasing idle inhibitor: {}.", reason);
+                break;
+            }
+        }
+        thread::sleep(Duration::from_millis(10));
     }
 
-    println!("\nShutting down, releasing idle inhibitor...");
+    if !running.load(Ordering::SeqCst) {
+        println!("\nShutting down, releasing idle inhibitor...");
+    }
+    inhibit.set_active(false)
+}
+
+// --- Automatic Mode ---
+
+/// How often audio playback is polled; window changes arrive as events.
+const AUDIO_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The `idle` section of config.jsonc, as far as automatic inhibition goes.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase", default)]
+struct AutoInhibitConfig {
+    inhibit_on_fullscreen: bool,
+    inhibit_on_audio: bool,
+    /// Window classes that keep the system awake while focused; `*` matches anything.
+    inhibit_classes: Vec<String>,
+}
 
-    if let Some(inhibitor) = state.inhibitor.take() {
-        inhibitor.destroy();
+impl Default for AutoInhibitConfig {
+    fn default() -> Self {
+        AutoInhibitConfig {
+            inhibit_on_fullscreen: true,
+            inhibit_on_audio: true,
+            inhibit_classes: Vec::new(),
+        }
     }
-    conn.flush().unwrap();
+}
 
-    Ok(())
+/// Why the system should stay awake right now, if it should.
+fn auto_inhibit_reason(ipc: &HyprlandIpc, config: &AutoInhibitConfig, audio_playing: bool) -> Result<Option<String>> {
+    if config.inhibit_on_fullscreen {
+        // Only fullscreen windows on a visible workspace count.
+        let visible: HashSet<i32> = ipc.monitors()?
+            .iter()
+            .flat_map(|m| [m.active_workspace.id, m.special_workspace.id])
+            .collect();
+        if let Some(client) = ipc.clients()?.iter().find(|c| c.fullscreen && visible.contains(&c.workspace.id)) {
+            return Ok(Some(format!("fullscreen: {}", client.class)));
+        }
+    }
+    if let Some(window) = ipc.active_window()?
+        && let Some(pattern) = config.inhibit_classes.iter().find(|p| glob::matches(p, &window.class))
+    {
+        return Ok(Some(format!("focused: {} ({})", window.class, pattern)));
+    }
+    if audio_playing {
+        return Ok(Some("audio playing".to_string()));
+    }
+    Ok(None)
+}
+
+/// True when any application is playing (not corked) audio.
+fn audio_playing() -> bool {
+    Command::new("pactl")
+        .args(["list", "sink-inputs"])
+        .stderr(Stdio::null())
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .any(|line| line.trim() == "Corked: no")
+        })
+        .unwrap_or(false)
+}
+
+/// Whether a Hyprland event can change the fullscreen or focus state.
+fn affects_auto_inhibit(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::Fullscreen { .. }
+            | Event::ActiveWindow { .. }
+            | Event::ActiveWindowV2 { .. }
+            | Event::Workspace { .. }
+            | Event::WorkspaceV2 { .. }
+            | Event::FocusedMon { .. }
+            | Event::ActiveSpecial { .. }
+            | Event::OpenWindow { .. }
+            | Event::CloseWindow { .. }
+            | Event::MoveWindow { .. }
+            | Event::MonitorRemoved { .. }
+    )
+}
+
+/// Holds an inhibitor only while a fullscreen window, audio playback or a
+/// configured window class calls for it.
+fn run_auto() -> Result<()> {
+    let config: AutoInhibitConfig = config::section("idle")?;
+    let socket = ControlSocket::bind()?;
+    let ipc = HyprlandIpc::from_env()?;
+    let mut inhibit = IdleInhibit::connect()?;
+
+    let (sender, events) = mpsc::channel();
+    let stream = EventStream::connect()?;
+    thread::spawn(move || {
+        for event in stream {
+            if sender.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut status = InhibitorStatus {
+        running: true,
+        mode: Some("auto".to_string()),
+        pid: Some(process::id()),
+        ..Default::default()
+    };
+    println!("Automatic idle inhibition running. Press Ctrl-C to exit.");
+
+    let running = exit_flag()?;
+    let mut playing = config.inhibit_on_audio && audio_playing();
+    let mut last_audio_check = Instant::now();
+    let mut dirty = true;
+    let mut retry_at: Option<Instant> = None;
+
+    while running.load(Ordering::SeqCst) {
+        inhibit.dispatch()?;
+
+        if socket.serve(&status) {
+            println!("Automatic idle inhibition turned off.");
+            break;
+        }
+
+        loop {
+            match events.try_recv() {
+                Ok(event) => dirty |= affects_auto_inhibit(&event?),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => bail!("Hyprland event socket closed"),
+            }
+        }
+        if config.inhibit_on_audio && last_audio_check.elapsed() >= AUDIO_CHECK_INTERVAL {
+            last_audio_check = Instant::now();
+            let now_playing = audio_playing();
+            dirty |= now_playing != playing;
+            playing = now_playing;
+        }
+
+        if dirty && retry_at.is_none_or(|at| Instant::now() >= at) {
+            // A failed query keeps the current state and is retried shortly.
+            match auto_inhibit_reason(&ipc, &config, playing) {
+                Ok(reason) => {
+                    dirty = false;
+                    retry_at = None;
+                    if reason.is_some() != inhibit.is_active() {
+                        match &reason {
+                            Some(reason) => println!("Inhibiting idle: {}.", reason),
+                            None => println!("Allowing idle."),
+                        }
+                        inhibit.set_active(reason.is_some())?;
+                        status.since = reason.as_ref().map(|_| Local::now());
+                    }
+                    status.active = reason.is_some();
+                    status.reason = reason;
+                }
+                Err(e) => {
+                    eprintln!("Failed to check for idle inhibition: {:#}", e);
+                    retry_at = Some(Instant::now() + CHECK_INTERVAL);
+                }
+            }
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    inhibit.set_active(false)
+}
+
+// --- Helper Functions ---
+
+/// Parses durations like `30m`, `1h30m`, `90s` or `45` (minutes).
+fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let too_long = || anyhow!("Duration is too long: {}", value);
+    if let Ok(minutes) = value.parse::<u64>()
+        && minutes > 0
+    {
+        return Ok(Duration::from_secs(minutes.checked_mul(60).ok_or_else(too_long)?));
+    }
+
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => bail!("Invalid duration unit '{}' in {} (use h, m or s)", c, value),
+        };
+        let amount: u64 = number.parse()
+            .map_err(|_| anyhow!("Invalid duration: {}", value))?;
+        total = amount.checked_mul(unit)
+            .and_then(|seconds| total.checked_add(seconds))
+            .ok_or_else(too_long)?;
+        number.clear();
+    }
+    if !number.is_empty() || total == 0 {
+        bail!("Invalid duration: {}", value);
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// The next occurrence of `HH:MM`, today or tomorrow.
+fn next_time_of_day(value: &str) -> Result<DateTime<Local>> {
+    next_time_of_day_after(value, Local::now())
+}
+
+fn next_time_of_day_after(value: &str, now: DateTime<Local>) -> Result<DateTime<Local>> {
+    let time = NaiveTime::parse_from_str(value, "%H:%M")
+        .with_context(|| format!("Invalid time: {} (expected HH:MM)", value))?;
+    let mut date = now.date_naive();
+    if time <= now.time() {
+        date = date + Days::new(1);
+    }
+    Local.from_local_datetime(&date.and_time(time))
+        .earliest()
+        .ok_or_else(|| anyhow!("{} does not exist in the local time zone", value))
+}
+
+fn pid_running(pid: u32) -> bool {
+    Path::new(&format!("/proc/{}", pid)).exists()
+}
+
+/// Matches `name` against each process's comm and executable name.
+fn process_running(name: &str) -> bool {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return false;
+    };
+    entries.filter_map(|e| e.ok()).any(|entry| {
+        let path = entry.path();
+        if !entry.file_name().to_string_lossy().bytes().all(|b| b.is_ascii_digit()) {
+            return false;
+        }
+        let comm = fs::read_to_string(path.join("comm")).unwrap_or_default();
+        if comm.trim() == name {
+            return true;
+        }
+        // comm is truncated to 15 bytes, so also check argv[0].
+        fs::read(path.join("cmdline"))
+            .ok()
+            .and_then(|cmdline| {
+                let arg0 = cmdline.split(|&b| b == 0).next()?.to_vec();
+                let arg0 = String::from_utf8(arg0).ok()?;
+                Some(Path::new(&arg0).file_name()?.to_string_lossy() == name)
+            })
+            .unwrap_or(false)
+    })
 }
 
 impl Dispatch<wl_registry::WlRegistry, ()> for AppState {
@@ -145,3 +816,61 @@ impl Dispatch<zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1, ()> for AppState {
         _: &QueueHandle<AppState>,
     ) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_accepts_minutes_and_units() {
+        assert_eq!(parse_duration("30").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration(" 90s ").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("1m30s").unwrap(), Duration::from_secs(90));
+    }
+
+    #[test]
+    fn parse_duration_rejects_bad_input() {
+        for value in ["", "0", "0m", "h", "10x", "1h30", "-5"] {
+            assert!(parse_duration(value).is_err(), "{:?} was accepted", value);
+        }
+    }
+
+    #[test]
+    fn parse_duration_rejects_overflow() {
+        assert!(parse_duration(&u64::MAX.to_string()).is_err());
+        assert!(parse_duration(&format!("{}h", u64::MAX / 2)).is_err());
+        assert!(parse_duration(&format!("{}s1s", u64::MAX)).is_err());
+    }
+
+    #[test]
+    fn from_args_rejects_out_of_range_durations() {
+        let args = ReleaseArgs {
+            duration: Some(Duration::from_secs(u64::MAX / 60)),
+            until: None,
+            while_pid: None,
+            while_process: None,
+        };
+        assert!(ReleaseConditions::from_args(&args).is_err());
+    }
+
+    fn local(date: &str) -> DateTime<Local> {
+        let naive = chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M").unwrap();
+        Local.from_local_datetime(&naive).earliest().unwrap()
+    }
+
+    #[test]
+    fn next_time_of_day_is_today_or_tomorrow() {
+        let now = local("2026-10-18 12:00");
+        assert_eq!(next_time_of_day_after("18:30", now).unwrap(), local("2026-10-18 18:30"));
+        assert_eq!(next_time_of_day_after("08:00", now).unwrap(), local("2026-10-19 08:00"));
+        assert_eq!(next_time_of_day_after("12:00", now).unwrap(), local("2026-10-19 12:00"));
+    }
+
+    #[test]
+    fn next_time_of_day_rejects_bad_times() {
+        let now = local("2026-10-18 12:00");
+        assert!(next_time_of_day_after("25:00", now).is_err());
+        assert!(next_time_of_day_after("noon", now).is_err());
+    }
+}
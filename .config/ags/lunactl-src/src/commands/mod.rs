@@ -1,6 +1,7 @@
 pub mod core;
 pub mod get_keybinds;
 pub mod hyprland;
+pub mod idle;
 pub mod idle_inhibitor;
 pub mod network;
 pub mod recorder;
@@ -0,0 +1,88 @@
+use anyhow::{Context, Result};
+use std::fs;
+
+/// Byte and packet counters of one interface, as found in `/proc/net/dev`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterfaceCounters {
+    pub name: String,
+    pub rx_bytes: u64,
+    pub rx_packets: u64,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+}
+
+/// Reads the counters of every interface.
+pub fn read() -> Result<Vec<InterfaceCounters>> {
+    let content = fs::read_to_string("/proc/net/dev")
+        .context("Failed to read /proc/net/dev")?;
+    Ok(parse(&content))
+}
+
+/// Parses `/proc/net/dev`: two header lines, then `iface: rx... tx...` with
+/// eight receive and eight transmit columns.
+pub fn parse(content: &str) -> Vec<InterfaceCounters> {
+    content
+        .lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, stats) = line.split_once(':')?;
+            let columns: Vec<u64> = stats
+                .split_whitespace()
+                .map(|column| column.parse().unwrap_or(0))
+                .collect();
+            if columns.len() < 10 {
+                return None;
+            }
+            Some(InterfaceCounters {
+                name: name.trim().to_string(),
+                rx_bytes: columns[0],
+                rx_packets: columns[1],
+                tx_bytes: columns[8],
+                tx_packets: columns[9],
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROC_NET_DEV: &str = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:  123456     789    0    0    0     0          0         0   123456     789    0    0    0     0       0          0
+wlp0s20f3:18446744073709551615 98765432    0   12    0     0          0      4321 9876543210987  1234567    0    0    0     0       0          0
+docker0:       0       0    0    0    0     0          0         0     5000      10    0    0    0     0       0          0
+";
+
+    #[test]
+    fn parses_every_interface_after_the_headers() {
+        let counters = parse(PROC_NET_DEV);
+        let names: Vec<_> = counters.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["lo", "wlp0s20f3", "docker0"]);
+        assert_eq!(counters[0], InterfaceCounters {
+            name: "lo".into(),
+            rx_bytes: 123456,
+            rx_packets: 789,
+            tx_bytes: 123456,
+            tx_packets: 789,
+        });
+    }
+
+    #[test]
+    fn parses_columns_that_run_into_the_name() {
+        let wifi = &parse(PROC_NET_DEV)[1];
+        assert_eq!(wifi.rx_bytes, u64::MAX);
+        assert_eq!(wifi.rx_packets, 98765432);
+        assert_eq!(wifi.tx_bytes, 9876543210987);
+        assert_eq!(wifi.tx_packets, 1234567);
+    }
+
+    #[test]
+    fn skips_short_and_empty_input() {
+        assert!(parse("").is_empty());
+        let truncated = "header\nheader\n  eth0: 1 2 3\n";
+        assert!(parse(truncated).is_empty());
+    }
+}
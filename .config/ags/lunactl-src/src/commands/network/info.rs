@@ -0,0 +1,257 @@
+use anyhow::{Result, anyhow, bail};
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::nmcli::{CommandRunner, Nmcli};
+
+/// Bounds the whole connectivity check, name resolution included.
+const CONNECTIVITY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Debug, Default)]
+pub struct NetworkInfo {
+    pub connected: bool,
+    /// NetworkManager device type, e.g. `wifi` or `ethernet`.
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub interface: Option<String>,
+    /// Name of the active connection profile.
+    pub connection: Option<String>,
+    pub ssid: Option<String>,
+    /// Wi-Fi signal strength in percent.
+    pub signal: Option<u8>,
+    pub ipv4: Vec<String>,
+    pub ipv6: Vec<String>,
+    pub gateway: Option<String>,
+    pub gateway6: Option<String>,
+    pub dns: Vec<String>,
+    pub connectivity: Option<ConnectivityCheck>,
+    pub wifi: WifiState,
+}
+
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct WifiState {
+    /// A Wi-Fi device exists.
+    pub present: bool,
+    /// The Wi-Fi radio is switched on.
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ConnectivityCheck {
+    pub endpoint: String,
+    pub online: bool,
+    pub status: Option<u16>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Describes the connection that carries the default route, or the first
+/// connected device if none has a gateway.
+pub fn collect<R: CommandRunner>(nmcli: &Nmcli<R>) -> Result<NetworkInfo> {
+    let devices = nmcli.devices()?;
+    let present = devices.iter().any(|d| d.kind == "wifi");
+    let wifi = WifiState { present, enabled: present && nmcli.wifi_enabled()? };
+    let devices: Vec<_> = devices.into_iter()
+        .filter(|d| d.is_connected() && d.kind != "loopback")
+        .collect();
+
+    let mut candidates = Vec::new();
+    for device in devices {
+        let properties = nmcli.device_properties(&device.device)?;
+        let has_gateway = properties.iter()
+            .any(|(key, value)| key.ends_with(".GATEWAY") && !value.is_empty() && value != "--");
+        candidates.push((device, properties, has_gateway));
+    }
+    let Some(index) = candidates.iter().position(|c| c.2).or((!candidates.is_empty()).then_some(0)) else {
+        return Ok(NetworkInfo { wifi, ..Default::default() });
+    };
+    let (device, properties, _) = candidates.swap_remove(index);
+
+    let values = |prefix: &str| -> Vec<String> {
+        properties.iter()
+            .filter(|(key, value)| key.starts_with(prefix) && !value.is_empty() && value != "--")
+            .map(|(_, value)| value.clone())
+            .collect()
+    };
+    let value = |key: &str| values(key).into_iter().next();
+
+    let on_wifi = device.kind == "wifi";
+    let mut dns = values("IP4.DNS[");
+    dns.extend(values("IP6.DNS["));
+
+    Ok(NetworkInfo {
+        connected: true,
+        ssid: if on_wifi { nmcli.active_ssid()? } else { None },
+        signal: if on_wifi { nmcli.active_signal()? } else { None },
+        ipv4: values("IP4.ADDRESS["),
+        ipv6: values("IP6.ADDRESS["),
+        gateway: value("IP4.GATEWAY"),
+        gateway6: value("IP6.GATEWAY"),
+        dns,
+        kind: Some(device.kind),
+        interface: Some(device.device),
+        connection: Some(device.connection),
+        connectivity: None,
+        wifi,
+    })
+}
+
+/// Sends a GET request to `endpoint` and reports whether a 2xx answer came back.
+pub fn check_connectivity(endpoint: &str) -> ConnectivityCheck {
+    let start = Instant::now();
+    let result = http_status(endpoint);
+    let latency_ms = start.elapsed().as_millis() as u64;
+    match result {
+        Ok(status) => ConnectivityCheck {
+            endpoint: endpoint.to_string(),
+            online: (200..300).contains(&status),
+            status: Some(status),
+            latency_ms: Some(latency_ms),
+            error: None,
+        },
+        Err(e) => ConnectivityCheck {
+            endpoint: endpoint.to_string(),
+            online: false,
+            status: None,
+            latency_ms: None,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+fn http_status(endpoint: &str) -> Result<u16> {
+    let Some(rest) = endpoint.strip_prefix("http://") else {
+        bail!("Only http:// connectivity endpoints are supported: {}", endpoint);
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+    let address = if authority.contains(':') { authority.to_string() } else { format!("{}:80", authority) };
+    let host = authority.split(':').next().unwrap_or(authority);
+
+    let deadline = Instant::now() + CONNECTIVITY_TIMEOUT;
+    let remaining = || deadline.saturating_duration_since(Instant::now()).max(Duration::from_millis(1));
+
+    let socket_address = resolve(&address, CONNECTIVITY_TIMEOUT)?
+        .ok_or_else(|| anyhow!("Could not resolve {}", host))?;
+    let mut stream = TcpStream::connect_timeout(&socket_address, remaining())?;
+    stream.set_read_timeout(Some(remaining()))?;
+    stream.set_write_timeout(Some(remaining()))?;
+    write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: lunactl\r\nConnection: close\r\n\r\n", path, host)?;
+
+    // Only the status line matters.
+    let mut buffer = [0u8; 64];
+    let read = stream.read(&mut buffer)?;
+    let response = String::from_utf8_lossy(&buffer[..read]);
+    response
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| anyhow!("Unexpected response from {}", endpoint))
+}
+
+/// Resolves `address` on a helper thread, since the system resolver has no
+/// timeout of its own. A lookup that takes too long is left to finish alone.
+fn resolve(address: &str, timeout: Duration) -> Result<Option<std::net::SocketAddr>> {
+    let (sender, receiver) = mpsc::channel();
+    let owned = address.to_string();
+    thread::spawn(move || {
+        let _ = sender.send(owned.to_socket_addrs().map(|mut addresses| addresses.next()));
+    });
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => Ok(result?),
+        Err(_) => bail!("Timed out resolving {}", address),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::network::nmcli::testing::MockRunner;
+
+    const DEVICES: &str = "nmcli -t -f DEVICE,TYPE,STATE,CONNECTION device status";
+    const RADIO: (&str, &str) = ("nmcli radio wifi", "enabled\n");
+    const WLAN0: (&str, &str) = (
+        "nmcli -t -e no device show wlan0",
+        "GENERAL.DEVICE:wlan0\n\
+         IP4.ADDRESS[1]:192.168.1.5/24\n\
+         IP4.GATEWAY:192.168.1.1\n\
+         IP4.DNS[1]:192.168.1.1\n\
+         IP6.ADDRESS[1]:fe80::1/64\n\
+         IP6.GATEWAY:\n\
+         IP6.DNS[1]:fe80::53\n",
+    );
+    const DOCKER0: (&str, &str) = (
+        "nmcli -t -e no device show docker0",
+        "IP4.ADDRESS[1]:172.17.0.1/16\n\
+         IP4.GATEWAY:--\n",
+    );
+    const SSID: (&str, &str) = ("nmcli -t -f ACTIVE,SSID device wifi", "no:Neighbour\nyes:Home\\: 5G\n");
+    const SIGNAL: (&str, &str) = (
+        "nmcli -t -f IN-USE,SIGNAL device wifi list --rescan no",
+        " :80\n*:64\n",
+    );
+
+    #[test]
+    fn collect_prefers_the_device_with_a_gateway() {
+        let runner = MockRunner::new(&[
+            (DEVICES, "lo:loopback:connected (externally):lo\n\
+                       docker0:bridge:connected (externally):docker0\n\
+                       wlan0:wifi:connected:Home 1\n"),
+            RADIO, WLAN0, DOCKER0, SSID, SIGNAL,
+        ]);
+        let info = collect(&Nmcli::with_runner(runner)).unwrap();
+
+        assert!(info.connected);
+        assert_eq!(info.kind.as_deref(), Some("wifi"));
+        assert_eq!(info.interface.as_deref(), Some("wlan0"));
+        assert_eq!(info.connection.as_deref(), Some("Home 1"));
+        assert_eq!(info.ssid.as_deref(), Some("Home: 5G"));
+        assert_eq!(info.signal, Some(64));
+        assert_eq!(info.ipv4, ["192.168.1.5/24"]);
+        assert_eq!(info.ipv6, ["fe80::1/64"]);
+        assert_eq!(info.gateway.as_deref(), Some("192.168.1.1"));
+        assert_eq!(info.gateway6, None);
+        assert_eq!(info.dns, ["192.168.1.1", "fe80::53"]);
+        assert_eq!(info.wifi, WifiState { present: true, enabled: true });
+    }
+
+    #[test]
+    fn collect_skips_wifi_details_on_ethernet() {
+        let runner = MockRunner::new(&[
+            (DEVICES, "enp3s0:ethernet:connected:Wired connection 1\n"),
+            ("nmcli -t -e no device show enp3s0", "IP4.ADDRESS[1]:10.0.0.2/8\nIP4.GATEWAY:10.0.0.1\n"),
+        ]);
+        let nmcli = Nmcli::with_runner(runner);
+        let info = collect(&nmcli).unwrap();
+
+        assert_eq!(info.kind.as_deref(), Some("ethernet"));
+        assert_eq!(info.ssid, None);
+        assert_eq!(info.wifi, WifiState { present: false, enabled: false });
+        // Neither the radio nor the access points are asked about
+        assert!(nmcli.runner().commands().iter().all(|c| !c.contains("wifi")));
+    }
+
+    #[test]
+    fn collect_reports_the_radio_while_disconnected() {
+        let runner = MockRunner::new(&[
+            (DEVICES, "wlan0:wifi:unavailable:\nlo:loopback:connected (externally):lo\n"),
+            ("nmcli radio wifi", "disabled\n"),
+        ]);
+        let info = collect(&Nmcli::with_runner(runner)).unwrap();
+
+        assert!(!info.connected);
+        assert_eq!(info.interface, None);
+        assert_eq!(info.wifi, WifiState { present: true, enabled: false });
+    }
+
+    #[test]
+    fn collect_returns_nmcli_errors() {
+        assert!(collect(&Nmcli::with_runner(MockRunner::new(&[]))).is_err());
+    }
+}
@@ -0,0 +1,472 @@
+use anyhow::{Context, Result, anyhow};
+use clap::{Args, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+use std::time::Instant;
+use std::{thread, time::Duration};
+
+use crate::config;
+use crate::glob;
+use crate::notify;
+
+const DEFAULT_USAGE_WARN_PERCENT: f64 = 90.0;
+
+mod counters;
+mod info;
+mod nmcli;
+mod usage;
+mod vpn;
+
+use counters::InterfaceCounters;
+use nmcli::Nmcli;
+
+/// The `network` section of config.jsonc.
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct NetworkConfig {
+    /// A plain http:// URL that answers with a 2xx status when online.
+    connectivity_url: Option<String>,
+    /// Monthly data caps in GiB, keyed by SSID or interface name.
+    #[serde(default)]
+    usage_caps: HashMap<String, f64>,
+    /// Warn once this share of a cap is used [default: 90]
+    usage_warn_percent: Option<f64>,
+}
+
+#[derive(Args, Debug)]
+pub struct NetworkArgs {
+    #[command(subcommand)]
+    command: NetworkCommands,
+}
+
+#[derive(Subcommand, Debug)]
+enum NetworkCommands {
+    /// Get the SSID of the current Wi-Fi network ("No Wi-Fi" without a Wi-Fi device)
+    Ssid,
+    /// Calculates network bandwidth for a given direction
+    Bandwidth(BandwidthArgs),
+    /// Describe the active connection and, if configured, check internet connectivity
+    Info {
+        /// Print the information as JSON
+        #[arg(long)]
+        json: bool,
+        /// Skip the connectivity check [endpoint: config.jsonc network.connectivityUrl, none by default]
+        #[arg(long)]
+        no_check: bool,
+    },
+    /// Manage Wi-Fi through NetworkManager
+    #[command(subcommand)]
+    Wifi(WifiCommands),
+    /// Data used per network today, this week and this month
+    Usage(UsageArgs),
+    /// Manage NetworkManager VPN and WireGuard profiles and wg-quick configs
+    #[command(subcommand)]
+    Vpn(VpnCommands),
+}
+
+/// Reports usage recorded by `usage record`, as of its last sample.
+#[derive(Args, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
+struct UsageArgs {
+    #[command(subcommand)]
+    command: Option<UsageCommands>,
+    /// Print the usage as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum UsageCommands {
+    /// Sample the interface counters into the usage history
+    Record {
+        /// Keep sampling every interval
+        #[arg(long)]
+        watch: bool,
+        /// Seconds between samples
+        #[arg(long, default_value_t = 60)]
+        interval: u64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum VpnCommands {
+    /// List every VPN and whether it is up
+    List {
+        /// Print the VPNs as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Bring a VPN up
+    Up { name: String },
+    /// Bring a VPN down
+    Down { name: String },
+    /// Show the VPNs that are up
+    Status {
+        /// Print the status as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum WifiCommands {
+    /// List visible networks, strongest first
+    List {
+        /// Print the networks as JSON
+        #[arg(long)]
+        json: bool,
+        /// List every access point instead of one entry per SSID
+        #[arg(long)]
+        all: bool,
+    },
+    /// Connect to a network, using its saved profile if there is one
+    Connect {
+        ssid: String,
+        /// Read the password for a new or changed network from stdin
+        #[arg(long)]
+        ask: bool,
+    },
+    /// Delete the saved profile of a network
+    Forget { ssid: String },
+    /// Turn the Wi-Fi radio on
+    On,
+    /// Turn the Wi-Fi radio off
+    Off,
+    /// Ask the Wi-Fi device to scan for networks
+    Rescan,
+}
+
+#[derive(Args, Debug)]
+struct BandwidthArgs {
+    /// 'recv' for received, 'sent' for sent
+    #[arg(index = 1, default_value = "recv")]
+    direction: String,
+    /// Keep printing a sample every interval
+    #[arg(long)]
+    watch: bool,
+    /// Milliseconds between samples
+    #[arg(long, default_value_t = 1000)]
+    interval: u64,
+    /// Print per-interface rates in both directions as JSON
+    #[arg(long)]
+    json: bool,
+    /// Only count these interfaces
+    #[arg(long, value_delimiter = ',')]
+    iface: Vec<String>,
+    /// Skip interfaces matching these patterns (`*` is a wildcard); lo is always skipped
+    #[arg(long, value_delimiter = ',', default_value = "docker*,veth*,br-*,virbr*")]
+    exclude: Vec<String>,
+    /// Report rates in bits or bytes per second
+    #[arg(long, value_enum, default_value_t = RateUnit::Bytes)]
+    unit: RateUnit,
+}
+
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum RateUnit {
+    Bits,
+    Bytes,
+}
+
+#[derive(Serialize, Debug)]
+struct InterfaceRate {
+    name: String,
+    /// Received per second, in `unit`.
+    rx: f64,
+    /// Sent per second, in `unit`.
+    tx: f64,
+}
+
+#[derive(Serialize, Debug)]
+struct BandwidthSample {
+    unit: RateUnit,
+    interfaces: Vec<InterfaceRate>,
+    total: InterfaceRate,
+}
+
+// --- Bandwidth Helper Functions ---
+fn is_counted(name: &str, args: &BandwidthArgs) -> bool {
+    if !args.iface.is_empty() {
+        return args.iface.iter().any(|iface| iface == name);
+    }
+    name != "lo" && !args.exclude.iter().any(|pattern| glob::matches(pattern, name))
+}
+
+fn read_counters(args: &BandwidthArgs) -> Result<Vec<InterfaceCounters>> {
+    let mut counters = counters::read()?;
+    counters.retain(|c| is_counted(&c.name, args));
+    Ok(counters)
+}
+
+/// Rates between two readings; interfaces that appeared in between are skipped.
+fn rates(before: &[InterfaceCounters], after: &[InterfaceCounters], secs: f64, unit: RateUnit) -> BandwidthSample {
+    let scale = match unit {
+        RateUnit::Bits => 8.0 / secs,
+        RateUnit::Bytes => 1.0 / secs,
+    };
+    let interfaces: Vec<InterfaceRate> = after
+        .iter()
+        .filter_map(|now| {
+            let then = before.iter().find(|c| c.name == now.name)?;
+            Some(InterfaceRate {
+                name: now.name.clone(),
+                // Counters restart when an interface is recreated.
+                rx: now.rx_bytes.saturating_sub(then.rx_bytes) as f64 * scale,
+                tx: now.tx_bytes.saturating_sub(then.tx_bytes) as f64 * scale,
+            })
+        })
+        .collect();
+    let total = InterfaceRate {
+        name: "total".to_string(),
+        rx: interfaces.iter().map(|i| i.rx).sum(),
+        tx: interfaces.iter().map(|i| i.tx).sum(),
+    };
+    BandwidthSample { unit, interfaces, total }
+}
+
+fn format_bandwidth(rate: f64, unit: RateUnit) -> String {
+    let (base, units) = match unit {
+        RateUnit::Bytes => (1024.0, ["B/s", "KB/s", "MB/s", "GB/s", "TB/s"]),
+        RateUnit::Bits => (1000.0, ["b/s", "Kb/s", "Mb/s", "Gb/s", "Tb/s"]),
+    };
+    if rate < base {
+        return format!("{} {}", rate.round() as u64, units[0]);
+    }
+    let mut bandwidth = rate / base;
+    let mut i = 1;
+    while bandwidth >= base && i < units.len() - 1 {
+        bandwidth /= base;
+        i += 1;
+    }
+    format!("{:.1} {}", bandwidth, units[i])
+}
+
+// --- Command Handlers ---
+fn handle_bandwidth(args: &BandwidthArgs) -> Result<()> {
+    if args.direction != "recv" && args.direction != "sent" {
+        return Err(anyhow!("Invalid direction. Use 'recv' or 'sent'"));
+    }
+    if args.interval == 0 {
+        return Err(anyhow!("Interval must be greater than zero"));
+    }
+    let interval = Duration::from_millis(args.interval);
+
+    let mut before = read_counters(args)
+        .context("Failed to get initial byte count")?;
+    let mut last = Instant::now();
+    let mut stdout = io::stdout().lock();
+
+    loop {
+        thread::sleep(interval);
+        let after = read_counters(args)
+            .context("Failed to get second byte count")?;
+        let sample = rates(&before, &after, last.elapsed().as_secs_f64(), args.unit);
+        last = Instant::now();
+        before = after;
+
+        let line = if args.json {
+            serde_json::to_string(&sample)?
+        } else if args.direction == "recv" {
+            format_bandwidth(sample.total.rx, args.unit)
+        } else {
+            format_bandwidth(sample.total.tx, args.unit)
+        };
+        // Stop quietly once the reader goes away.
+        if writeln!(stdout, "{}", line).and_then(|_| stdout.flush()).is_err() || !args.watch {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Reads one line from stdin, prompting first when it is a terminal.
+fn read_password() -> Result<String> {
+    let stdin = io::stdin();
+    if stdin.is_terminal() {
+        eprint!("Password: ");
+    }
+    let mut line = String::new();
+    stdin.read_line(&mut line).context("Failed to read the password from stdin")?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn handle_wifi(command: &WifiCommands) -> Result<()> {
+    let nmcli = Nmcli::new();
+    match command {
+        WifiCommands::List { json, all } => {
+            let networks = nmcli.wifi_list(*all)?;
+            if *json {
+                println!("{}", serde_json::to_string(&networks)?);
+                return Ok(());
+            }
+            for network in networks {
+                let marker = if network.in_use { "*" } else { " " };
+                let security = if network.security.is_empty() { "open" } else { &network.security };
+                println!("{} {:>3}%  {:<32} {}", marker, network.signal, network.ssid, security);
+            }
+        }
+        WifiCommands::Connect { ssid, ask } => {
+            let password = if *ask { Some(read_password()?) } else { None };
+            nmcli.connect(ssid, password.as_deref())?;
+            println!("Connected to {}.", ssid);
+        }
+        WifiCommands::Forget { ssid } => {
+            nmcli.forget(ssid)?;
+            println!("Forgot {}.", ssid);
+        }
+        WifiCommands::On => nmcli.set_wifi_enabled(true)?,
+        WifiCommands::Off => nmcli.set_wifi_enabled(false)?,
+        WifiCommands::Rescan => nmcli.rescan()?,
+    }
+    Ok(())
+}
+
+fn handle_vpn(command: &VpnCommands) -> Result<()> {
+    let nmcli = Nmcli::new();
+    match command {
+        VpnCommands::List { json } => {
+            let connections = vpn::list(&nmcli)?;
+            if *json {
+                println!("{}", serde_json::to_string(&connections)?);
+                return Ok(());
+            }
+            for connection in connections {
+                let state = if connection.active { "up" } else { "down" };
+                println!("{:<4} {:<24} {}", state, connection.name, connection.kind);
+            }
+        }
+        VpnCommands::Up { name } => {
+            vpn::up(&nmcli, name)?;
+            println!("{} is up.", name);
+        }
+        VpnCommands::Down { name } => {
+            vpn::down(&nmcli, name)?;
+            println!("{} is down.", name);
+        }
+        VpnCommands::Status { json } => {
+            let status = vpn::status(vpn::list(&nmcli)?);
+            if *json {
+                println!("{}", serde_json::to_string(&status)?);
+            } else if !status.active {
+                println!("No VPN is up");
+            } else {
+                for connection in status.connections {
+                    println!("{} ({})", connection.name, connection.device.as_deref().unwrap_or("-"));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn handle_usage(args: &UsageArgs) -> Result<()> {
+    let config: NetworkConfig = config::section("network")?;
+    let warn_percent = config.usage_warn_percent.unwrap_or(DEFAULT_USAGE_WARN_PERCENT);
+    if let Some(UsageCommands::Record { watch, interval }) = &args.command {
+        let nmcli = Nmcli::new();
+        loop {
+            usage::update(&nmcli, &config.usage_caps, warn_percent)?;
+            if !watch {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_secs((*interval).max(1)));
+        }
+    }
+
+    let networks = usage::report(&config.usage_caps, warn_percent)?;
+    if args.json {
+        println!("{}", serde_json::to_string(&networks)?);
+        return Ok(());
+    }
+    if networks.is_empty() {
+        println!("No usage recorded yet; run `lunactl network usage record --watch` in the background.");
+        return Ok(());
+    }
+    println!("{:<24} {:>10} {:>10} {:>10}  cap", "network", "today", "week", "month");
+    for network in networks {
+        let cap = match (network.cap_bytes, network.cap_percent) {
+            (Some(cap), Some(percent)) => {
+                let warning = if network.cap_warning { " !" } else { "" };
+                format!("{} ({}%){}", notify::format_size(cap), percent, warning)
+            }
+            _ => "-".to_string(),
+        };
+        println!(
+            "{:<24} {:>10} {:>10} {:>10}  {}",
+            network.network,
+            notify::format_size(network.today.total()),
+            notify::format_size(network.week.total()),
+            notify::format_size(network.month.total()),
+            cap,
+        );
+    }
+    Ok(())
+}
+
+fn handle_ssid() -> Result<()> {
+    let nmcli = Nmcli::new();
+    if !nmcli.devices()?.iter().any(|d| d.kind == "wifi") {
+        println!("No Wi-Fi");
+        return Ok(());
+    }
+    let ssid = nmcli.active_ssid()?;
+    println!("{}", ssid.as_deref().unwrap_or("Disconnected"));
+    Ok(())
+}
+
+fn handle_info(json: bool, no_check: bool) -> Result<()> {
+    let mut info = info::collect(&Nmcli::new())?;
+    if !no_check && info.connected {
+        // Nothing is contacted unless an endpoint was configured
+        let config: NetworkConfig = config::section("network")?;
+        if let Some(endpoint) = &config.connectivity_url {
+            info.connectivity = Some(info::check_connectivity(endpoint));
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string(&info)?);
+        return Ok(());
+    }
+    if !info.connected {
+        println!("Disconnected");
+        return Ok(());
+    }
+    let show = |label: &str, value: Option<&str>| {
+        if let Some(value) = value {
+            println!("{:<13}{}", label, value);
+        }
+    };
+    show("Type:", info.kind.as_deref());
+    show("Interface:", info.interface.as_deref());
+    show("Connection:", info.connection.as_deref());
+    show("SSID:", info.ssid.as_deref());
+    show("Signal:", info.signal.map(|s| format!("{}%", s)).as_deref());
+    show("Wi-Fi:", info.wifi.present.then_some(if info.wifi.enabled { "on" } else { "off" }));
+    show("IPv4:", Some(info.ipv4.join(", ")).filter(|s| !s.is_empty()).as_deref());
+    show("IPv6:", Some(info.ipv6.join(", ")).filter(|s| !s.is_empty()).as_deref());
+    show("Gateway:", info.gateway.as_deref().or(info.gateway6.as_deref()));
+    show("DNS:", Some(info.dns.join(", ")).filter(|s| !s.is_empty()).as_deref());
+    if let Some(check) = &info.connectivity {
+        let result = match (check.online, check.latency_ms, &check.error) {
+            (true, Some(ms), _) => format!("online ({} ms)", ms),
+            (_, _, Some(error)) => format!("offline ({})", error),
+            _ => format!("offline (HTTP {})", check.status.unwrap_or_default()),
+        };
+        show("Internet:", Some(&result));
+    }
+    Ok(())
+}
+
+pub fn handle_network_command(args: &NetworkArgs, _debug: bool) -> Result<()> {
+    match &args.command {
+        NetworkCommands::Bandwidth(bandwidth_args) => handle_bandwidth(bandwidth_args)?,
+        NetworkCommands::Ssid => handle_ssid()?,
+        NetworkCommands::Info { json, no_check } => handle_info(*json, *no_check)?,
+        NetworkCommands::Wifi(command) => handle_wifi(command)?,
+        NetworkCommands::Vpn(command) => handle_vpn(command)?,
+        NetworkCommands::Usage(args) => handle_usage(args)?,
+    }
+    Ok(())
+}
@@ -0,0 +1,476 @@
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs a program and returns its stdout. Everything that talks to
+/// NetworkManager goes through this, so a mock can stand in for nmcli.
+pub trait CommandRunner {
+    fn run(&self, program: &str, args: &[&str]) -> Result<String>;
+
+    /// Like `run`, with `input` written to the program's stdin. Used for
+    /// secrets, which must not show up in the process list.
+    fn run_with_input(&self, program: &str, args: &[&str], input: &str) -> Result<String>;
+}
+
+/// Runs commands for real.
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, program: &str, args: &[&str]) -> Result<String> {
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .with_context(|| format!("Failed to execute {}", program))?;
+        stdout_of(program, output)
+    }
+
+    fn run_with_input(&self, program: &str, args: &[&str], input: &str) -> Result<String> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to execute {}", program))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes())
+                .with_context(|| format!("Failed to write to {}", program))?;
+        }
+        let output = child.wait_with_output()
+            .with_context(|| format!("Failed to execute {}", program))?;
+        stdout_of(program, output)
+    }
+}
+
+fn stdout_of(program: &str, output: Output) -> Result<String> {
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.trim().trim_start_matches("Error: ");
+        bail!("{} failed: {}", program, message);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    pub bssid: String,
+    /// Signal strength in percent.
+    pub signal: u8,
+    /// e.g. `WPA2`, or empty for open networks.
+    pub security: String,
+    pub channel: u32,
+    pub frequency_mhz: u32,
+    /// Currently connected.
+    pub in_use: bool,
+    /// A saved connection profile exists for this SSID.
+    pub known: bool,
+}
+
+/// A saved NetworkManager connection profile.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ConnectionProfile {
+    pub name: String,
+    pub uuid: String,
+    /// NetworkManager type, e.g. `802-11-wireless` or `vpn`.
+    pub kind: String,
+    /// The device it is active on, empty if inactive.
+    pub device: String,
+}
+
+/// A network device as listed by `nmcli device status`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DeviceStatus {
+    pub device: String,
+    /// e.g. `wifi`, `ethernet` or `loopback`.
+    pub kind: String,
+    pub state: String,
+    pub connection: String,
+}
+
+impl DeviceStatus {
+    /// Includes `connected (externally)`, e.g. interfaces set up by other tools.
+    pub fn is_connected(&self) -> bool {
+        self.state.starts_with("connected")
+    }
+}
+
+const WIRELESS: &str = "802-11-wireless";
+
+/// NetworkManager through its `nmcli` command line client.
+pub struct Nmcli<R: CommandRunner = SystemRunner> {
+    runner: R,
+}
+
+impl Nmcli {
+    pub fn new() -> Self {
+        Nmcli::with_runner(SystemRunner)
+    }
+}
+
+impl<R: CommandRunner> Nmcli<R> {
+    pub fn with_runner(runner: R) -> Self {
+        Nmcli { runner }
+    }
+
+    /// The runner, for other tools that belong next to nmcli calls.
+    pub fn runner(&self) -> &R {
+        &self.runner
+    }
+
+    fn nmcli(&self, args: &[&str]) -> Result<String> {
+        self.runner.run("nmcli", args)
+    }
+
+    /// Visible networks, strongest first. Unless `all` is set, each SSID is
+    /// listed once with its strongest access point.
+    pub fn wifi_list(&self, all: bool) -> Result<Vec<WifiNetwork>> {
+        let output = self.nmcli(&[
+            "-t", "-f", "IN-USE,SSID,BSSID,SIGNAL,SECURITY,CHAN,FREQ", "device", "wifi", "list",
+        ])?;
+        let known: HashSet<String> = self.wifi_profiles()?
+            .into_iter()
+            .map(|(ssid, _)| ssid)
+            .collect();
+
+        let mut networks: Vec<WifiNetwork> = output
+            .lines()
+            .filter_map(|line| parse_wifi_line(line, &known))
+            .collect();
+        networks.sort_by(|a, b| b.in_use.cmp(&a.in_use).then(b.signal.cmp(&a.signal)));
+
+        if !all {
+            let mut seen = HashSet::new();
+            networks.retain(|n| seen.insert(n.ssid.clone()));
+        }
+        Ok(networks)
+    }
+
+    /// The SSID of the active Wi-Fi connection.
+    pub fn active_ssid(&self) -> Result<Option<String>> {
+        let output = self.nmcli(&["-t", "-f", "ACTIVE,SSID", "device", "wifi"])?;
+        Ok(output.lines()
+            .map(split_terse)
+            .find(|fields| fields.first().is_some_and(|f| f == "yes"))
+            .and_then(|fields| fields.into_iter().nth(1)))
+    }
+
+    /// Signal strength of the connected access point, in percent.
+    pub fn active_signal(&self) -> Result<Option<u8>> {
+        let output = self.nmcli(&["-t", "-f", "IN-USE,SIGNAL", "device", "wifi", "list", "--rescan", "no"])?;
+        Ok(output.lines()
+            .map(split_terse)
+            .find(|fields| fields.first().is_some_and(|f| f == "*"))
+            .and_then(|fields| fields.get(1)?.parse().ok()))
+    }
+
+    pub fn devices(&self) -> Result<Vec<DeviceStatus>> {
+        let output = self.nmcli(&["-t", "-f", "DEVICE,TYPE,STATE,CONNECTION", "device", "status"])?;
+        Ok(output.lines()
+            .map(split_terse)
+            .filter(|fields| fields.len() >= 4)
+            .map(|mut fields| DeviceStatus {
+                connection: fields.remove(3),
+                state: fields.remove(2),
+                kind: fields.remove(1),
+                device: fields.remove(0),
+            })
+            .collect())
+    }
+
+    /// The `nmcli device show` properties of a device, e.g.
+    /// `("IP4.ADDRESS[1]", "192.168.1.5/24")`, in order.
+    pub fn device_properties(&self, device: &str) -> Result<Vec<(String, String)>> {
+        // Unescaped, since keys never contain colons but IPv6 values do.
+        let output = self.nmcli(&["-t", "-e", "no", "device", "show", device])?;
+        Ok(output.lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect())
+    }
+
+    pub fn connections(&self) -> Result<Vec<ConnectionProfile>> {
+        let output = self.nmcli(&["-t", "-f", "NAME,UUID,TYPE,DEVICE", "connection", "show"])?;
+        Ok(output.lines()
+            .map(split_terse)
+            .filter(|fields| fields.len() >= 4)
+            .map(|mut fields| ConnectionProfile {
+                device: fields.remove(3),
+                kind: fields.remove(2),
+                uuid: fields.remove(1),
+                name: fields.remove(0),
+            })
+            .collect())
+    }
+
+    /// Saved Wi-Fi profiles with the SSID each one connects to. Profile
+    /// names are only labels and often differ from the SSID (`Home 1`).
+    pub fn wifi_profiles(&self) -> Result<Vec<(String, ConnectionProfile)>> {
+        let mut profiles = Vec::new();
+        for profile in self.connections()?.into_iter().filter(|c| c.kind == WIRELESS) {
+            let output = self.nmcli(&["-t", "-g", "802-11-wireless.ssid", "connection", "show", "uuid", &profile.uuid])?;
+            let ssid = output.lines().next().map(split_terse).and_then(|f| f.into_iter().next()).unwrap_or_default();
+            profiles.push((ssid, profile));
+        }
+        Ok(profiles)
+    }
+
+    /// Activates the saved profile for `ssid`, or creates one. A password
+    /// always creates a fresh profile so a changed key can be entered; it
+    /// is answered on nmcli's stdin rather than passed as an argument.
+    pub fn connect(&self, ssid: &str, password: Option<&str>) -> Result<()> {
+        let saved = self.wifi_profiles()?
+            .into_iter()
+            .find(|(profile_ssid, _)| profile_ssid == ssid);
+        match (password, saved) {
+            (None, Some((_, profile))) => self.nmcli(&["connection", "up", "uuid", &profile.uuid])?,
+            (None, None) => self.nmcli(&["device", "wifi", "connect", ssid])?,
+            (Some(password), _) => self.runner.run_with_input(
+                "nmcli",
+                &["--ask", "device", "wifi", "connect", ssid],
+                &format!("{}\n", password),
+            )?,
+        };
+        Ok(())
+    }
+
+    /// Deletes every saved profile for `ssid`.
+    pub fn forget(&self, ssid: &str) -> Result<()> {
+        let uuids: Vec<String> = self.wifi_profiles()?
+            .into_iter()
+            .filter(|(profile_ssid, _)| profile_ssid == ssid)
+            .map(|(_, profile)| profile.uuid)
+            .collect();
+        if uuids.is_empty() {
+            bail!("No saved Wi-Fi network named {}", ssid);
+        }
+        for uuid in uuids {
+            self.nmcli(&["connection", "delete", "uuid", &uuid])?;
+        }
+        Ok(())
+    }
+
+    pub fn connection_up(&self, uuid: &str) -> Result<()> {
+        self.nmcli(&["connection", "up", "uuid", uuid])?;
+        Ok(())
+    }
+
+    pub fn connection_down(&self, uuid: &str) -> Result<()> {
+        self.nmcli(&["connection", "down", "uuid", uuid])?;
+        Ok(())
+    }
+
+    /// Whether the Wi-Fi radio is switched on.
+    pub fn wifi_enabled(&self) -> Result<bool> {
+        let output = self.nmcli(&["radio", "wifi"])?;
+        Ok(output.trim() == "enabled")
+    }
+
+    pub fn set_wifi_enabled(&self, enabled: bool) -> Result<()> {
+        self.nmcli(&["radio", "wifi", if enabled { "on" } else { "off" }])?;
+        Ok(())
+    }
+
+    pub fn rescan(&self) -> Result<()> {
+        self.nmcli(&["device", "wifi", "rescan"])?;
+        Ok(())
+    }
+}
+
+fn parse_wifi_line(line: &str, known: &HashSet<String>) -> Option<WifiNetwork> {
+    let fields = split_terse(line);
+    let [in_use, ssid, bssid, signal, security, channel, frequency] = fields.as_slice() else {
+        return None;
+    };
+    // Hidden networks have no SSID to connect to by name.
+    if ssid.is_empty() {
+        return None;
+    }
+    Some(WifiNetwork {
+        ssid: ssid.clone(),
+        bssid: bssid.clone(),
+        signal: signal.parse().unwrap_or(0),
+        security: if security == "--" { String::new() } else { security.clone() },
+        channel: channel.parse().unwrap_or(0),
+        frequency_mhz: frequency.split_whitespace().next()?.parse().unwrap_or(0),
+        in_use: in_use == "*",
+        known: known.contains(ssid),
+    })
+}
+
+/// Splits a line of `nmcli -t` output, where `:` separates fields and
+/// literal colons and backslashes are escaped with a backslash.
+pub fn split_terse(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => fields.last_mut().unwrap().extend(chars.next()),
+            ':' => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// A stand-in for nmcli, shared by the tests of the network modules.
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Answers nmcli calls from canned output keyed by the joined arguments,
+    /// and records every call with what was written to stdin.
+    pub struct MockRunner {
+        replies: Vec<(&'static str, &'static str)>,
+        pub calls: RefCell<Vec<(String, Option<String>)>>,
+    }
+
+    impl MockRunner {
+        pub fn new(replies: &[(&'static str, &'static str)]) -> Self {
+            MockRunner { replies: replies.to_vec(), calls: RefCell::new(Vec::new()) }
+        }
+
+        fn reply(&self, program: &str, args: &[&str], input: Option<&str>) -> Result<String> {
+            let command = format!("{} {}", program, args.join(" "));
+            self.calls.borrow_mut().push((command.clone(), input.map(str::to_string)));
+            match self.replies.iter().find(|(c, _)| *c == command) {
+                Some((_, output)) => Ok(output.to_string()),
+                None => bail!("unexpected command: {}", command),
+            }
+        }
+
+        /// The commands run so far, without their stdin.
+        pub fn commands(&self) -> Vec<String> {
+            self.calls.borrow().iter().map(|(c, _)| c.clone()).collect()
+        }
+    }
+
+    impl CommandRunner for MockRunner {
+        fn run(&self, program: &str, args: &[&str]) -> Result<String> {
+            self.reply(program, args, None)
+        }
+
+        fn run_with_input(&self, program: &str, args: &[&str], input: &str) -> Result<String> {
+            self.reply(program, args, Some(input))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::testing::MockRunner;
+
+    const CONNECTIONS: (&str, &str) = (
+        "nmcli -t -f NAME,UUID,TYPE,DEVICE connection show",
+        "Home 1:1111:802-11-wireless:wlan0\n\
+         Wired connection 1:2222:802-3-ethernet:\n\
+         Cafe\\: Guest:3333:802-11-wireless:\n\
+         work-vpn:4444:vpn:\n",
+    );
+    const HOME_SSID: (&str, &str) = ("nmcli -t -g 802-11-wireless.ssid connection show uuid 1111", "Home\n");
+    const CAFE_SSID: (&str, &str) = ("nmcli -t -g 802-11-wireless.ssid connection show uuid 3333", "Cafe\\: Guest\n");
+
+    #[test]
+    fn split_terse_unescapes_fields() {
+        assert_eq!(split_terse("*:Cafe\\: Guest:AA\\:BB:C\\\\D"), ["*", "Cafe: Guest", "AA:BB", "C\\D"]);
+        assert_eq!(split_terse(":"), ["", ""]);
+        assert_eq!(split_terse(""), [""]);
+    }
+
+    #[test]
+    fn connections_are_parsed() {
+        let nmcli = Nmcli::with_runner(MockRunner::new(&[CONNECTIONS]));
+        let connections = nmcli.connections().unwrap();
+        assert_eq!(connections.len(), 4);
+        assert_eq!(connections[0], ConnectionProfile {
+            name: "Home 1".into(),
+            uuid: "1111".into(),
+            kind: WIRELESS.into(),
+            device: "wlan0".into(),
+        });
+        assert_eq!(connections[2].name, "Cafe: Guest");
+        assert_eq!(connections[3].device, "");
+    }
+
+    #[test]
+    fn wifi_list_dedupes_and_marks_known_by_ssid() {
+        let runner = MockRunner::new(&[
+            (
+                "nmcli -t -f IN-USE,SSID,BSSID,SIGNAL,SECURITY,CHAN,FREQ device wifi list",
+                " :Neighbour:AA\\:00:80:WPA2:6:2437 MHz\n\
+                 *:Home:AA\\:01:60:WPA2 WPA3:36:5180 MHz\n\
+                 \x20:Home:AA\\:02:70:WPA2:1:2412 MHz\n\
+                 \x20::AA\\:03:90:WPA2:11:2462 MHz\n\
+                 \x20:Cafe\\: Guest:AA\\:04:40:--:11:2462 MHz\n",
+            ),
+            CONNECTIONS,
+            HOME_SSID,
+            CAFE_SSID,
+        ]);
+        let nmcli = Nmcli::with_runner(runner);
+
+        let networks = nmcli.wifi_list(false).unwrap();
+        let ssids: Vec<_> = networks.iter().map(|n| n.ssid.as_str()).collect();
+        assert_eq!(ssids, ["Home", "Neighbour", "Cafe: Guest"]);
+        assert!(networks[0].in_use && networks[0].known);
+        assert_eq!(networks[0].bssid, "AA:01");
+        assert_eq!(networks[0].frequency_mhz, 5180);
+        assert!(!networks[1].known);
+        assert!(networks[2].known);
+        assert_eq!(networks[2].security, "");
+
+        assert_eq!(nmcli.wifi_list(true).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn connect_uses_the_profile_for_the_ssid() {
+        let runner = MockRunner::new(&[CONNECTIONS, HOME_SSID, CAFE_SSID, ("nmcli connection up uuid 1111", "")]);
+        let nmcli = Nmcli::with_runner(runner);
+        nmcli.connect("Home", None).unwrap();
+        assert_eq!(nmcli.runner().commands().last().unwrap(), "nmcli connection up uuid 1111");
+    }
+
+    #[test]
+    fn connect_creates_a_profile_for_new_networks() {
+        let runner = MockRunner::new(&[CONNECTIONS, HOME_SSID, CAFE_SSID, ("nmcli device wifi connect Home 1", "")]);
+        let nmcli = Nmcli::with_runner(runner);
+        // A profile named like the SSID does not count
+        nmcli.connect("Home 1", None).unwrap();
+        assert_eq!(nmcli.runner().commands().last().unwrap(), "nmcli device wifi connect Home 1");
+    }
+
+    #[test]
+    fn connect_passes_the_password_on_stdin() {
+        let runner = MockRunner::new(&[CONNECTIONS, HOME_SSID, CAFE_SSID, ("nmcli --ask device wifi connect Home", "")]);
+        let nmcli = Nmcli::with_runner(runner);
+        nmcli.connect("Home", Some("hunter2")).unwrap();
+
+        let calls = nmcli.runner().calls.borrow();
+        let (command, input) = calls.last().unwrap();
+        assert_eq!(command, "nmcli --ask device wifi connect Home");
+        assert_eq!(input.as_deref(), Some("hunter2\n"));
+        assert!(calls.iter().all(|(c, _)| !c.contains("hunter2")));
+    }
+
+    #[test]
+    fn forget_deletes_profiles_by_ssid() {
+        let runner = MockRunner::new(&[CONNECTIONS, HOME_SSID, CAFE_SSID, ("nmcli connection delete uuid 3333", "")]);
+        let nmcli = Nmcli::with_runner(runner);
+        nmcli.forget("Cafe: Guest").unwrap();
+        assert_eq!(nmcli.runner().commands().last().unwrap(), "nmcli connection delete uuid 3333");
+
+        let error = nmcli.forget("Home 1").unwrap_err();
+        assert_eq!(error.to_string(), "No saved Wi-Fi network named Home 1");
+    }
+
+    #[test]
+    fn runner_errors_are_returned() {
+        let nmcli = Nmcli::with_runner(MockRunner::new(&[]));
+        assert!(nmcli.connections().is_err());
+        assert!(nmcli.forget("Home").is_err());
+    }
+}
@@ -0,0 +1,332 @@
+use anyhow::Result;
+use chrono::{Datelike, Days, Local, NaiveDate};
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+use super::counters::{self, InterfaceCounters};
+use super::nmcli::{CommandRunner, Nmcli};
+use crate::{glob, notify, state};
+
+/// Tunnels and bridges carry traffic that is already counted on the
+/// physical interface.
+const SKIPPED_INTERFACES: [&str; 7] = ["lo", "docker*", "veth*", "br-*", "virbr*", "tun*", "wg*"];
+/// Days of history kept in the state file.
+const RETENTION_DAYS: u64 = 400;
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub struct Traffic {
+    pub rx: u64,
+    pub tx: u64,
+}
+
+impl Traffic {
+    fn add(&mut self, other: Traffic) {
+        self.rx += other.rx;
+        self.tx += other.tx;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.rx + self.tx
+    }
+}
+
+/// Recorded usage, stored in `$XDG_STATE_HOME/lunactl/network-usage.json`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+struct UsageState {
+    /// Counters restart from zero on every boot.
+    boot_id: String,
+    /// Interface counters at the last sample.
+    last: HashMap<String, Traffic>,
+    /// Bytes per day (`YYYY-MM-DD`) and network.
+    days: BTreeMap<String, HashMap<String, Traffic>>,
+    /// Networks already warned about per month (`YYYY-MM`).
+    warned: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct NetworkUsage {
+    /// The SSID for Wi-Fi, the interface name otherwise.
+    pub network: String,
+    pub today: Traffic,
+    pub week: Traffic,
+    pub month: Traffic,
+    pub cap_bytes: Option<u64>,
+    /// Share of the monthly cap used, in percent.
+    pub cap_percent: Option<f64>,
+    pub cap_warning: bool,
+}
+
+fn state_path() -> Result<PathBuf> {
+    Ok(state::state_dir()?.join("network-usage.json"))
+}
+
+fn boot_id() -> String {
+    fs::read_to_string("/proc/sys/kernel/random/boot_id")
+        .map(|id| id.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Maps each interface to the network its traffic is billed to.
+fn network_names<R: CommandRunner>(nmcli: &Nmcli<R>) -> HashMap<String, String> {
+    let Ok(devices) = nmcli.devices() else {
+        return HashMap::new();
+    };
+    let ssid = nmcli.active_ssid().ok().flatten();
+    devices
+        .into_iter()
+        .filter(|d| d.kind == "wifi" && d.is_connected())
+        .filter_map(|d| Some((d.device, ssid.clone()?)))
+        .collect()
+}
+
+/// Bytes moved since `last`. A counter below its last value means the
+/// interface was recreated, and a new boot ID means a reboot; either way
+/// the counter started again from zero.
+fn delta(now: &InterfaceCounters, last: Option<&Traffic>, rebooted: bool) -> Traffic {
+    match last {
+        Some(last) if !rebooted && now.rx_bytes >= last.rx && now.tx_bytes >= last.tx => Traffic {
+            rx: now.rx_bytes - last.rx,
+            tx: now.tx_bytes - last.tx,
+        },
+        Some(_) => Traffic { rx: now.rx_bytes, tx: now.tx_bytes },
+        None if rebooted => Traffic { rx: now.rx_bytes, tx: now.tx_bytes },
+        // First time this interface is seen in this boot: only take a baseline.
+        None => Traffic::default(),
+    }
+}
+
+/// Adds the traffic since the last sample to today's totals.
+fn record_sample<R: CommandRunner>(nmcli: &Nmcli<R>) -> Result<UsageState> {
+    let path = state_path()?;
+    let mut usage: UsageState = state::load_json(&path)?;
+    let first_run = usage.boot_id.is_empty();
+    let boot_id = boot_id();
+    let rebooted = !first_run && usage.boot_id != boot_id;
+
+    let names = network_names(nmcli);
+    let today = Local::now().date_naive();
+    let day = usage.days.entry(today.to_string()).or_default();
+
+    let mut last = HashMap::new();
+    for counters in counters::read()? {
+        if SKIPPED_INTERFACES.iter().any(|pattern| glob::matches(pattern, &counters.name)) {
+            continue;
+        }
+        if !first_run {
+            let traffic = delta(&counters, usage.last.get(&counters.name), rebooted);
+            let network = names.get(&counters.name).unwrap_or(&counters.name);
+            day.entry(network.clone()).or_default().add(traffic);
+        }
+        last.insert(counters.name.clone(), Traffic { rx: counters.rx_bytes, tx: counters.tx_bytes });
+    }
+    usage.last = last;
+    usage.boot_id = boot_id;
+
+    if let Some(cutoff) = today.checked_sub_days(Days::new(RETENTION_DAYS)) {
+        let cutoff = cutoff.to_string();
+        usage.days.retain(|date, _| *date >= cutoff);
+    }
+
+    state::save_json(&path, &usage)?;
+    Ok(usage)
+}
+
+/// Per-network totals for the day, week (from Monday) and month containing `today`.
+fn summarize(usage: &UsageState, caps: &HashMap<String, f64>, warn_percent: f64, today: NaiveDate) -> Vec<NetworkUsage> {
+    let week_start = today - Days::new(today.weekday().num_days_from_monday() as u64);
+    let month_start = today.with_day(1).unwrap_or(today);
+
+    let mut networks: BTreeMap<&str, NetworkUsage> = BTreeMap::new();
+    for (date, day) in &usage.days {
+        let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") else {
+            continue;
+        };
+        for (network, traffic) in day.iter().filter(|(_, t)| t.total() > 0) {
+            let entry = networks.entry(network).or_insert_with(|| NetworkUsage {
+                network: network.clone(),
+                today: Traffic::default(),
+                week: Traffic::default(),
+                month: Traffic::default(),
+                cap_bytes: None,
+                cap_percent: None,
+                cap_warning: false,
+            });
+            if date == today {
+                entry.today.add(*traffic);
+            }
+            if date >= week_start && date <= today {
+                entry.week.add(*traffic);
+            }
+            if date >= month_start && date <= today {
+                entry.month.add(*traffic);
+            }
+        }
+    }
+
+    let mut networks: Vec<NetworkUsage> = networks.into_values().collect();
+    for network in &mut networks {
+        if let Some(cap) = caps.get(&network.network) {
+            let cap_bytes = (cap * GIB) as u64;
+            let percent = network.month.total() as f64 / cap_bytes.max(1) as f64 * 100.0;
+            network.cap_bytes = Some(cap_bytes);
+            network.cap_percent = Some((percent * 10.0).round() / 10.0);
+            network.cap_warning = percent >= warn_percent;
+        }
+    }
+    networks.sort_by_key(|n| std::cmp::Reverse(n.month.total()));
+    networks
+}
+
+/// Notifies once per month for each network past its warning threshold.
+fn warn_about_caps(usage: &mut UsageState, networks: &[NetworkUsage]) -> Result<()> {
+    let month = Local::now().format("%Y-%m").to_string();
+    let warned = usage.warned.entry(month).or_default();
+    let mut changed = false;
+    for network in networks.iter().filter(|n| n.cap_warning) {
+        if warned.contains(&network.network) {
+            continue;
+        }
+        // A missing notification daemon should not break recording; the
+        // warning is retried on the next sample.
+        let shown = Notification::new()
+            .summary("Data cap warning")
+            .body(&format!(
+                "{} has used {} of {} this month ({:.0}%)",
+                network.network,
+                notify::format_size(network.month.total()),
+                notify::format_size(network.cap_bytes.unwrap_or_default()),
+                network.cap_percent.unwrap_or_default(),
+            ))
+            .appname("lunactl")
+            .show();
+        if let Err(e) = shown {
+            eprintln!("Failed to show notification: {}", e);
+            continue;
+        }
+        warned.push(network.network.clone());
+        changed = true;
+    }
+    // Only the current month matters.
+    let keep = usage.warned.keys().next_back().cloned();
+    usage.warned.retain(|m, _| Some(m) == keep.as_ref());
+    if changed {
+        state::save_json(&state_path()?, &*usage)?;
+    }
+    Ok(())
+}
+
+/// Records a sample and warns about caps.
+pub fn update<R: CommandRunner>(nmcli: &Nmcli<R>, caps: &HashMap<String, f64>, warn_percent: f64) -> Result<()> {
+    let mut usage = record_sample(nmcli)?;
+    let networks = summarize(&usage, caps, warn_percent, Local::now().date_naive());
+    warn_about_caps(&mut usage, &networks)
+}
+
+/// Per-network totals as of the last recorded sample. Only reads the
+/// state file, so it is safe to run next to `usage record --watch`.
+pub fn report(caps: &HashMap<String, f64>, warn_percent: f64) -> Result<Vec<NetworkUsage>> {
+    let usage: UsageState = state::load_json(&state_path()?)?;
+    Ok(summarize(&usage, caps, warn_percent, Local::now().date_naive()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counters(rx_bytes: u64, tx_bytes: u64) -> InterfaceCounters {
+        InterfaceCounters { name: "wlan0".into(), rx_bytes, rx_packets: 0, tx_bytes, tx_packets: 0 }
+    }
+
+    fn traffic(rx: u64, tx: u64) -> Traffic {
+        Traffic { rx, tx }
+    }
+
+    #[test]
+    fn delta_counts_growth_since_the_last_sample() {
+        let t = delta(&counters(1500, 700), Some(&traffic(1000, 200)), false);
+        assert_eq!((t.rx, t.tx), (500, 500));
+    }
+
+    #[test]
+    fn delta_takes_a_baseline_on_the_first_sample() {
+        let t = delta(&counters(1500, 700), None, false);
+        assert_eq!(t.total(), 0);
+    }
+
+    #[test]
+    fn delta_counts_from_zero_after_a_reboot() {
+        // Even when the new counters happen to be above the old ones
+        let t = delta(&counters(1500, 700), Some(&traffic(1000, 200)), true);
+        assert_eq!((t.rx, t.tx), (1500, 700));
+        let t = delta(&counters(1500, 700), None, true);
+        assert_eq!((t.rx, t.tx), (1500, 700));
+    }
+
+    #[test]
+    fn delta_counts_from_zero_when_the_interface_was_recreated() {
+        let t = delta(&counters(300, 5000), Some(&traffic(1000, 200)), false);
+        assert_eq!((t.rx, t.tx), (300, 5000));
+    }
+
+    fn usage(days: &[(&str, &str, u64)]) -> UsageState {
+        let mut usage = UsageState::default();
+        for (date, network, bytes) in days {
+            usage.days.entry(date.to_string()).or_default().insert(network.to_string(), traffic(*bytes, 0));
+        }
+        usage
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn summarize_splits_weeks_and_months_at_their_boundaries() {
+        // 2026-10-01 is a Thursday, so its week started in September.
+        let usage = usage(&[
+            ("2026-09-27", "Home", 1),
+            ("2026-09-28", "Home", 10),
+            ("2026-09-30", "Home", 100),
+            ("2026-10-01", "Home", 1000),
+            ("2026-10-02", "Home", 10000),
+            ("2026-10-01", "eth0", 0),
+            ("not a date", "Home", 100000),
+        ]);
+        let networks = summarize(&usage, &HashMap::new(), 90.0, date("2026-10-01"));
+
+        assert_eq!(networks.len(), 1);
+        let home = &networks[0];
+        assert_eq!(home.network, "Home");
+        assert_eq!(home.today.total(), 1000);
+        assert_eq!(home.week.total(), 1110);
+        assert_eq!(home.month.total(), 1000);
+        assert_eq!(home.cap_bytes, None);
+    }
+
+    #[test]
+    fn summarize_applies_caps_and_sorts_by_month() {
+        let gib = GIB as u64;
+        let usage = usage(&[
+            ("2026-10-10", "Home", gib),
+            ("2026-10-12", "Phone", gib / 2),
+            ("2026-10-14", "Phone", gib * 9 / 20),
+            ("2026-10-14", "eth0", gib * 3),
+        ]);
+        let caps = HashMap::from([("Phone".to_string(), 1.0), ("Home".to_string(), 100.0)]);
+        let networks = summarize(&usage, &caps, 90.0, date("2026-10-14"));
+
+        let names: Vec<_> = networks.iter().map(|n| n.network.as_str()).collect();
+        assert_eq!(names, ["eth0", "Home", "Phone"]);
+        assert_eq!(networks[0].cap_percent, None);
+        assert_eq!(networks[1].cap_percent, Some(1.0));
+        assert!(!networks[1].cap_warning);
+        assert_eq!(networks[2].cap_bytes, Some(gib));
+        assert_eq!(networks[2].cap_percent, Some(95.0));
+        assert!(networks[2].cap_warning);
+    }
+}
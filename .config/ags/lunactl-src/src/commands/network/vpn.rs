@@ -0,0 +1,229 @@
+use anyhow::{Result, anyhow, bail};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use super::nmcli::{CommandRunner, ConnectionProfile, Nmcli};
+
+const WIREGUARD_DIR: &str = "/etc/wireguard";
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum VpnBackend {
+    NetworkManager,
+    WgQuick,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct VpnConnection {
+    pub name: String,
+    pub backend: VpnBackend,
+    /// `vpn` (NetworkManager plugins such as OpenVPN) or `wireguard`.
+    pub kind: String,
+    pub active: bool,
+    /// NetworkManager profile UUID.
+    pub uuid: Option<String>,
+    /// The interface carrying the tunnel while active.
+    pub device: Option<String>,
+}
+
+/// Reported by `network vpn status --json`.
+#[derive(Serialize, Debug)]
+pub struct VpnStatus {
+    /// Some VPN is up.
+    pub active: bool,
+    /// The VPNs that are up.
+    pub connections: Vec<VpnConnection>,
+}
+
+/// NetworkManager VPN and WireGuard profiles, followed by wg-quick configs
+/// that NetworkManager does not manage.
+pub fn list<R: CommandRunner>(nmcli: &Nmcli<R>) -> Result<Vec<VpnConnection>> {
+    Ok(merge(nmcli.connections()?, wg_quick_interfaces()))
+}
+
+/// Combines the NetworkManager profiles with wg-quick interfaces, given as
+/// their name and whether they are up.
+fn merge(profiles: Vec<ConnectionProfile>, wg_quick: Vec<(String, bool)>) -> Vec<VpnConnection> {
+    let mut connections: Vec<VpnConnection> = profiles
+        .into_iter()
+        .filter(|c| c.kind == "vpn" || c.kind == "wireguard")
+        .map(|c| VpnConnection {
+            active: !c.device.is_empty(),
+            device: Some(c.device).filter(|d| !d.is_empty()),
+            uuid: Some(c.uuid),
+            kind: c.kind,
+            name: c.name,
+            backend: VpnBackend::NetworkManager,
+        })
+        .collect();
+
+    for (name, active) in wg_quick {
+        if connections.iter().any(|c| c.name == name || c.device.as_deref() == Some(&name)) {
+            continue;
+        }
+        connections.push(VpnConnection {
+            device: active.then(|| name.clone()),
+            name,
+            backend: VpnBackend::WgQuick,
+            kind: "wireguard".to_string(),
+            active,
+            uuid: None,
+        });
+    }
+    connections
+}
+
+/// Configs in /etc/wireguard and whether their interface exists. The
+/// directory is usually readable by root only, so running WireGuard
+/// interfaces are added from sysfs as well.
+fn wg_quick_interfaces() -> Vec<(String, bool)> {
+    let mut names: Vec<String> = fs::read_dir(WIREGUARD_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let path = e.path();
+                    (path.extension()? == "conf").then(|| path.file_stem()?.to_str().map(str::to_string))?
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if let Ok(entries) = fs::read_dir("/sys/class/net") {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let uevent = fs::read_to_string(entry.path().join("uevent")).unwrap_or_default();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if uevent.lines().any(|line| line == "DEVTYPE=wireguard") && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names.sort();
+    names.into_iter()
+        .map(|name| {
+            let active = Path::new("/sys/class/net").join(&name).exists();
+            (name, active)
+        })
+        .collect()
+}
+
+pub fn up<R: CommandRunner>(nmcli: &Nmcli<R>, name: &str) -> Result<()> {
+    switch(nmcli, list(nmcli)?, name, true)
+}
+
+pub fn down<R: CommandRunner>(nmcli: &Nmcli<R>, name: &str) -> Result<()> {
+    switch(nmcli, list(nmcli)?, name, false)
+}
+
+/// Brings the VPN called `name` among `connections` up or down.
+fn switch<R: CommandRunner>(nmcli: &Nmcli<R>, connections: Vec<VpnConnection>, name: &str, up: bool) -> Result<()> {
+    let connection = connections.into_iter()
+        .find(|c| c.name == name)
+        .ok_or_else(|| anyhow!("No VPN named {} (see `lunactl network vpn list`)", name))?;
+    if connection.active == up {
+        bail!("{} is {}", name, if up { "already up" } else { "not up" });
+    }
+    match (connection.backend, &connection.uuid, up) {
+        (VpnBackend::NetworkManager, Some(uuid), true) => nmcli.connection_up(uuid),
+        (VpnBackend::NetworkManager, Some(uuid), false) => nmcli.connection_down(uuid),
+        // wg-quick needs root; pkexec asks for it graphically.
+        _ => nmcli.runner().run("pkexec", &["wg-quick", if up { "up" } else { "down" }, name]).map(|_| ()),
+    }
+}
+
+/// The VPNs among `connections` that are up.
+pub fn status(connections: Vec<VpnConnection>) -> VpnStatus {
+    let connections: Vec<_> = connections.into_iter().filter(|c| c.active).collect();
+    VpnStatus { active: !connections.is_empty(), connections }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::network::nmcli::testing::MockRunner;
+
+    const CONNECTIONS: (&str, &str) = (
+        "nmcli -t -f NAME,UUID,TYPE,DEVICE connection show",
+        "Home 1:1111:802-11-wireless:wlan0\n\
+         work-vpn:4444:vpn:\n\
+         home-wg:5555:wireguard:wg-home\n",
+    );
+
+    fn wg_quick() -> Vec<(String, bool)> {
+        // wg-home is managed by NetworkManager and listed once
+        vec![("mullvad".to_string(), false), ("wg-home".to_string(), true), ("office".to_string(), true)]
+    }
+
+    fn connections(runner: MockRunner) -> (Nmcli<MockRunner>, Vec<VpnConnection>) {
+        let nmcli = Nmcli::with_runner(runner);
+        let connections = merge(nmcli.connections().unwrap(), wg_quick());
+        (nmcli, connections)
+    }
+
+    #[test]
+    fn list_merges_profiles_and_wg_quick() {
+        let (_, connections) = connections(MockRunner::new(&[CONNECTIONS]));
+        let found: Vec<_> = connections.iter()
+            .map(|c| (c.name.as_str(), c.backend, c.kind.as_str(), c.active, c.device.as_deref()))
+            .collect();
+        assert_eq!(found, [
+            ("work-vpn", VpnBackend::NetworkManager, "vpn", false, None),
+            ("home-wg", VpnBackend::NetworkManager, "wireguard", true, Some("wg-home")),
+            ("mullvad", VpnBackend::WgQuick, "wireguard", false, None),
+            ("office", VpnBackend::WgQuick, "wireguard", true, Some("office")),
+        ]);
+        assert_eq!(connections[0].uuid.as_deref(), Some("4444"));
+        assert_eq!(connections[2].uuid, None);
+    }
+
+    #[test]
+    fn up_and_down_use_nmcli_for_profiles() {
+        let runner = MockRunner::new(&[
+            CONNECTIONS,
+            ("nmcli connection up uuid 4444", ""),
+            ("nmcli connection down uuid 5555", ""),
+        ]);
+        let (nmcli, connections) = connections(runner);
+        switch(&nmcli, connections.clone(), "work-vpn", true).unwrap();
+        switch(&nmcli, connections, "home-wg", false).unwrap();
+        assert_eq!(nmcli.runner().commands()[1..], ["nmcli connection up uuid 4444", "nmcli connection down uuid 5555"]);
+    }
+
+    #[test]
+    fn up_and_down_use_wg_quick_for_configs() {
+        let runner = MockRunner::new(&[
+            CONNECTIONS,
+            ("pkexec wg-quick up mullvad", ""),
+            ("pkexec wg-quick down office", ""),
+        ]);
+        let (nmcli, connections) = connections(runner);
+        switch(&nmcli, connections.clone(), "mullvad", true).unwrap();
+        switch(&nmcli, connections, "office", false).unwrap();
+        assert_eq!(nmcli.runner().commands()[1..], ["pkexec wg-quick up mullvad", "pkexec wg-quick down office"]);
+    }
+
+    #[test]
+    fn up_and_down_check_the_current_state() {
+        let (nmcli, connections) = connections(MockRunner::new(&[CONNECTIONS]));
+        let error = |name, up| switch(&nmcli, connections.clone(), name, up).unwrap_err().to_string();
+        assert_eq!(error("home-wg", true), "home-wg is already up");
+        assert_eq!(error("work-vpn", false), "work-vpn is not up");
+        assert_eq!(error("Home 1", true), "No VPN named Home 1 (see `lunactl network vpn list`)");
+        assert_eq!(nmcli.runner().commands().len(), 1);
+    }
+
+    #[test]
+    fn status_lists_the_active_vpns() {
+        let (_, connections) = connections(MockRunner::new(&[CONNECTIONS]));
+        let up = status(connections);
+        assert!(up.active);
+        let names: Vec<_> = up.connections.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["home-wg", "office"]);
+
+        let runner = MockRunner::new(&[(CONNECTIONS.0, "work-vpn:4444:vpn:\n")]);
+        let nmcli = Nmcli::with_runner(runner);
+        let idle = status(merge(nmcli.connections().unwrap(), Vec::new()));
+        assert!(!idle.active && idle.connections.is_empty());
+    }
+}
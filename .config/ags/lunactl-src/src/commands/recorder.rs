@@ -1,10 +1,17 @@
-use anyhow::{Context, Result, anyhow};
-use clap::{Args, Subcommand};
+use anyhow::{Context, Result, anyhow, bail};
+use clap::{Args, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use chrono::Local;
-use xdg_user;
+use std::time::Duration;
+use std::{fs, thread};
+use chrono::{DateTime, Local};
 use notify_rust::Notification;
-use std::fs;
+
+use crate::config;
+use crate::hyprland_ipc::HyprlandIpc;
+use crate::notify;
+use crate::state;
 
 #[derive(Args, Debug)]
 pub struct RecorderArgs {
@@ -15,21 +22,385 @@ pub struct RecorderArgs {
 #[derive(Subcommand, Debug, PartialEq)]
 pub enum RecorderCommand {
     /// Toggles the recording state (starts if not running, stops if running).
-    Toggle { 
-        /// Record the entire focused screen instead of selecting an area.
+    Toggle(StartArgs),
+    /// Starts a recording.
+    Start(StartArgs),
+    /// Stops the current recording.
+    Stop,
+    /// Shows whether lunactl is recording.
+    Status {
+        /// Print the status as JSON.
+        #[clap(long)]
+        json: bool,
+    },
+    /// Pauses or resumes the current recording.
+    Pause,
+    /// Reports which encoder, device and container a recording would use.
+    Check {
+        #[clap(flatten)]
+        encoder: EncoderArgs,
+        /// Print the result as JSON.
+        #[clap(long)]
+        json: bool,
+    },
+    /// Lists the audio sources that can be passed to --audio.
+    Sources {
+        /// Print the sources as JSON.
         #[clap(long)]
-        fullscreen: bool 
+        json: bool,
     },
 }
 
-fn is_process_running(process_name: &str) -> bool {
-    Command::new("pidof")
-        .arg(process_name)
-        .output()
-        .map(|o| o.status.success())
+#[derive(Args, Debug, PartialEq)]
+pub struct StartArgs {
+    /// Record the entire focused screen instead of selecting an area.
+    #[clap(long)]
+    pub fullscreen: bool,
+    /// Audio to record: none, system, mic, both or a source name
+    /// [default: the last choice, then config.jsonc recorder.audio, then system]
+    #[clap(long)]
+    pub audio: Option<String>,
+    /// Convert the area recording to a GIF when it stops and copy it.
+    #[clap(long, conflicts_with_all = ["fullscreen", "webm_clip"])]
+    pub gif: bool,
+    /// Convert the area recording to a silent WebM clip when it stops and copy it.
+    #[clap(long, conflicts_with = "fullscreen")]
+    pub webm_clip: bool,
+    /// Framerate of the converted clip [default: config.jsonc recorder.clip.fps, or 15]
+    #[clap(long)]
+    pub clip_fps: Option<u32>,
+    /// Maximum width of the converted clip [default: config.jsonc recorder.clip.maxWidth, or 720]
+    #[clap(long)]
+    pub clip_width: Option<u32>,
+    #[clap(flatten)]
+    pub encoder: EncoderArgs,
+}
+
+/// Encoder options; each falls back to config.jsonc `recorder.*`, then auto-detection.
+#[derive(Args, Debug, PartialEq)]
+pub struct EncoderArgs {
+    /// Video codec.
+    #[clap(long, value_enum)]
+    pub codec: Option<Codec>,
+    /// Hardware (vaapi) or software encoding.
+    #[clap(long, value_enum)]
+    pub encoder: Option<EncoderKind>,
+    /// Container format, which also sets the file extension.
+    #[clap(long, value_enum)]
+    pub container: Option<Container>,
+    /// Constant output framerate.
+    #[clap(long)]
+    pub framerate: Option<u32>,
+    /// Quality as CRF (software) or QP (vaapi); lower is better.
+    #[clap(long)]
+    pub quality: Option<u32>,
+    /// VAAPI render node, e.g. /dev/dri/renderD129.
+    #[clap(long)]
+    pub device: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    H264,
+    Hevc,
+    Vp9,
+    Av1,
+}
+
+impl Codec {
+    fn vaapi_encoder(self) -> &'static str {
+        match self {
+            Codec::H264 => "h264_vaapi",
+            Codec::Hevc => "hevc_vaapi",
+            Codec::Vp9 => "vp9_vaapi",
+            Codec::Av1 => "av1_vaapi",
+        }
+    }
+
+    fn software_encoder(self) -> &'static str {
+        match self {
+            Codec::H264 => "libx264",
+            Codec::Hevc => "libx265",
+            Codec::Vp9 => "libvpx-vp9",
+            Codec::Av1 => "libsvtav1",
+        }
+    }
+
+    /// The profile prefix vainfo prints for this codec.
+    fn va_profile(self) -> &'static str {
+        match self {
+            Codec::H264 => "VAProfileH264",
+            Codec::Hevc => "VAProfileHEVC",
+            Codec::Vp9 => "VAProfileVP9",
+            Codec::Av1 => "VAProfileAV1",
+        }
+    }
+}
+
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EncoderKind {
+    Auto,
+    Vaapi,
+    Software,
+}
+
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Container {
+    Mp4,
+    Mkv,
+    Webm,
+}
+
+impl Container {
+    fn extension(self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4",
+            Container::Mkv => "mkv",
+            Container::Webm => "webm",
+        }
+    }
+
+    /// wf-recorder defaults to aac, which WebM cannot hold.
+    fn audio_codec(self) -> Option<&'static str> {
+        match self {
+            Container::Webm => Some("libopus"),
+            Container::Mp4 | Container::Mkv => None,
+        }
+    }
+}
+
+/// The `recorder` section of config.jsonc.
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct RecorderConfig {
+    codec: Option<Codec>,
+    encoder: Option<EncoderKind>,
+    container: Option<Container>,
+    framerate: Option<u32>,
+    quality: Option<u32>,
+    device: Option<PathBuf>,
+    audio: Option<String>,
+    #[serde(default)]
+    clip: ClipConfig,
+}
+
+/// The `recorder.clip` section of config.jsonc.
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct ClipConfig {
+    fps: Option<u32>,
+    max_width: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum ClipFormat {
+    Gif,
+    Webm,
+}
+
+impl ClipFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ClipFormat::Gif => "gif",
+            ClipFormat::Webm => "webm",
+        }
+    }
+
+    fn mime_type(self) -> &'static str {
+        match self {
+            ClipFormat::Gif => "image/gif",
+            ClipFormat::Webm => "video/webm",
+        }
+    }
+}
+
+/// A clip to produce from the recording once it stops.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ClipRequest {
+    format: ClipFormat,
+    fps: u32,
+    max_width: u32,
+}
+
+/// Choices remembered between recordings, stored in `$XDG_STATE_HOME/lunactl/recorder.json`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct RecorderPrefs {
+    audio: Option<String>,
+}
+
+/// What to record audio from.
+#[derive(Debug, Clone, PartialEq)]
+enum AudioChoice {
+    None,
+    System,
+    Mic,
+    Both,
+    Source(String),
+}
+
+impl AudioChoice {
+    fn parse(value: &str) -> Self {
+        match value {
+            "none" => AudioChoice::None,
+            "system" => AudioChoice::System,
+            "mic" => AudioChoice::Mic,
+            "both" => AudioChoice::Both,
+            name => AudioChoice::Source(name.to_string()),
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            AudioChoice::None => "none",
+            AudioChoice::System => "system",
+            AudioChoice::Mic => "mic",
+            AudioChoice::Both => "both",
+            AudioChoice::Source(name) => name,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct AudioSource {
+    name: String,
+    /// True for sink monitors, which capture what is playing.
+    monitor: bool,
+    default: bool,
+}
+
+/// The resolved encoder setup for a recording.
+#[derive(Serialize, Debug)]
+struct EncoderPlan {
+    codec: Codec,
+    encoder: String,
+    device: Option<PathBuf>,
+    container: Container,
+    framerate: Option<u32>,
+    quality: Option<u32>,
+    /// Why this encoder was chosen.
+    reason: String,
+}
+
+impl EncoderPlan {
+    fn apply(&self, cmd: &mut Command) {
+        cmd.arg("-c").arg(&self.encoder);
+        match &self.device {
+            Some(device) => {
+                cmd.arg("-d").arg(device);
+            }
+            // Most players can't decode the 4:4:4 output software encoders default to.
+            None => {
+                cmd.args(["-x", "yuv420p"]);
+            }
+        }
+        if let Some(audio_codec) = self.container.audio_codec() {
+            cmd.arg("-C").arg(audio_codec);
+        }
+        if let Some(framerate) = self.framerate {
+            cmd.arg("-r").arg(framerate.to_string());
+        }
+        if let Some(quality) = self.quality {
+            let param = if self.device.is_some() { "qp" } else { "crf" };
+            cmd.arg("-p").arg(format!("{}={}", param, quality));
+            if self.codec == Codec::Vp9 && self.device.is_none() {
+                // libvpx only honors crf in constant-quality mode.
+                cmd.args(["-p", "b=0"]);
+            }
+        }
+    }
+}
+
+/// A recording started by lunactl, stored in `$XDG_RUNTIME_DIR/lunactl/recorder.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct RecordingState {
+    /// The wf-recorder writing the current segment; gone while paused.
+    pid: u32,
+    output: PathBuf,
+    started_at: DateTime<Local>,
+    /// Set while the recording is paused.
+    paused_at: Option<DateTime<Local>>,
+    /// Time spent paused before `paused_at`, in seconds.
+    paused_secs: i64,
+    /// PulseAudio modules loaded to mix system audio and mic, unloaded on stop.
+    #[serde(default)]
+    audio_modules: Vec<u32>,
+    #[serde(default)]
+    clip: Option<ClipRequest>,
+    /// wf-recorder arguments other than the output file, reused for the
+    /// segment started on resume.
+    #[serde(default)]
+    recorder_args: Vec<String>,
+    /// Finished segments of a paused recording, joined into `output` on stop.
+    #[serde(default)]
+    segments: Vec<PathBuf>,
+}
+
+impl RecordingState {
+    /// The file wf-recorder is writing to: `output` until the first pause,
+    /// numbered segments after that.
+    fn current_file(&self) -> PathBuf {
+        if self.segments.is_empty() {
+            self.output.clone()
+        } else {
+            segment_path(&self.output, self.segments.len())
+        }
+    }
+
+    /// Recorded time so far, excluding pauses.
+    fn elapsed_secs(&self) -> i64 {
+        let end = self.paused_at.unwrap_or_else(Local::now);
+        ((end - self.started_at).num_seconds() - self.paused_secs).max(0)
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct RecorderStatus {
+    recording: bool,
+    paused: bool,
+    pid: Option<u32>,
+    output: Option<PathBuf>,
+    started_at: Option<DateTime<Local>>,
+    elapsed_secs: i64,
+}
+
+fn state_path() -> Result<PathBuf> {
+    Ok(state::runtime_dir()?.join("recorder.json"))
+}
+
+/// Checks that `pid` is still a wf-recorder process, so a reused PID is not
+/// mistaken for our recording.
+fn is_recorder_alive(pid: u32) -> bool {
+    fs::read_to_string(format!("/proc/{}/comm", pid))
+        .map(|comm| comm.trim() == "wf-recorder")
         .unwrap_or(false)
 }
 
+/// Loads the recording lunactl started, discarding stale state.
+fn load_recording() -> Result<Option<RecordingState>> {
+    let path = state_path()?;
+    let recording: Option<RecordingState> = state::load_json(&path)?;
+    match recording {
+        Some(recording) if recording.paused_at.is_some() || is_recorder_alive(recording.pid) => Ok(Some(recording)),
+        Some(recording) => {
+            // wf-recorder died on its own; the audio modules it used are
+            // still loaded.
+            unload_audio_modules(&recording.audio_modules);
+            fs::remove_file(&path)?;
+            Ok(None)
+        }
+        None => Ok(None),
+    }
+}
+
+fn send_signal(pid: u32, signal: &str) -> Result<()> {
+    get_command_output(Command::new("kill").args(["-s", signal, &pid.to_string()]))?;
+    Ok(())
+}
+
 fn get_command_output(command: &mut Command) -> Result<String> {
     let output = command.output()
         .with_context(|| format!("Failed to execute command: {}", command.get_program().to_string_lossy()))?;
@@ -42,40 +413,166 @@ fn get_command_output(command: &mut Command) -> Result<String> {
     }
 }
 
-fn get_audio_source() -> Result<String> {
-    let output = get_command_output(
-        &mut Command::new("pactl").args(["list", "short", "sources"])
-    )?;
-    output.lines()
-        .find(|line| line.contains("monitor"))
-        .and_then(|line| line.split('\t').next())
-        .map(|s| s.to_string())
+// --- Audio Sources ---
+
+const MIX_SINK: &str = "lunactl_recorder_mix";
+
+fn pactl(args: &[&str]) -> Result<String> {
+    get_command_output(Command::new("pactl").args(args))
+}
+
+fn list_audio_sources() -> Result<Vec<AudioSource>> {
+    let default = pactl(&["get-default-source"]).unwrap_or_default();
+    let output = pactl(&["list", "short", "sources"])?;
+    Ok(output.lines()
+        .filter_map(|line| line.split('\t').nth(1))
+        .filter(|name| !name.starts_with(MIX_SINK))
+        .map(|name| AudioSource {
+            name: name.to_string(),
+            monitor: name.ends_with(".monitor"),
+            default: name == default,
+        })
+        .collect())
+}
+
+/// The monitor of the default sink, or the first monitor source.
+fn system_audio_source(sources: &[AudioSource]) -> Result<String> {
+    if let Ok(sink) = pactl(&["get-default-sink"]) {
+        let monitor = format!("{}.monitor", sink);
+        if sources.iter().any(|s| s.name == monitor) {
+            return Ok(monitor);
+        }
+    }
+    sources.iter()
+        .find(|s| s.monitor)
+        .map(|s| s.name.clone())
         .ok_or_else(|| anyhow!("No audio monitor source found"))
 }
 
+fn mic_audio_source(sources: &[AudioSource]) -> Result<String> {
+    sources.iter()
+        .find(|s| s.default && !s.monitor)
+        .or_else(|| sources.iter().find(|s| !s.monitor))
+        .map(|s| s.name.clone())
+        .ok_or_else(|| anyhow!("No microphone found"))
+}
+
+fn prefs_path() -> Result<PathBuf> {
+    Ok(state::state_dir()?.join("recorder.json"))
+}
+
+/// Picks the audio choice from the flag, the last recording, config, or
+/// `system`.
+fn resolve_audio_choice(flag: Option<&str>) -> Result<AudioChoice> {
+    if let Some(value) = flag {
+        return Ok(AudioChoice::parse(value));
+    }
+    let prefs: RecorderPrefs = state::load_json(&prefs_path()?)?;
+    let config: RecorderConfig = config::section("recorder")?;
+    let value = prefs.audio.or(config.audio);
+    Ok(value.as_deref().map_or(AudioChoice::System, AudioChoice::parse))
+}
+
+/// Remembers an explicit `--audio` choice for the next recording. Called
+/// once wf-recorder is running, so a typo or failed start is not kept.
+fn remember_audio_choice(value: &str) -> Result<()> {
+    let path = prefs_path()?;
+    let mut prefs: RecorderPrefs = state::load_json(&path)?;
+    prefs.audio = Some(value.to_string());
+    state::save_json(&path, &prefs)
+}
+
+/// Resolves the choice to a source wf-recorder can read, returning the
+/// modules loaded to create it.
+fn prepare_audio(choice: &AudioChoice) -> Result<(Option<String>, Vec<u32>)> {
+    if *choice == AudioChoice::None {
+        return Ok((None, Vec::new()));
+    }
+    let sources = list_audio_sources()?;
+    match choice {
+        AudioChoice::None => Ok((None, Vec::new())),
+        AudioChoice::System => Ok((Some(system_audio_source(&sources)?), Vec::new())),
+        AudioChoice::Mic => Ok((Some(mic_audio_source(&sources)?), Vec::new())),
+        AudioChoice::Source(name) => {
+            if !sources.iter().any(|s| &s.name == name) {
+                bail!("Unknown audio source: {} (see `lunactl recorder sources`)", name);
+            }
+            Ok((Some(name.clone()), Vec::new()))
+        }
+        AudioChoice::Both => {
+            let system = system_audio_source(&sources)?;
+            let mic = mic_audio_source(&sources)?;
+            let modules = create_audio_mix(&[&system, &mic])?;
+            Ok((Some(format!("{}.monitor", MIX_SINK)), modules))
+        }
+    }
+}
+
+/// wf-recorder reads a single source, so system audio and mic are looped
+/// into a null sink whose monitor is recorded instead.
+fn create_audio_mix(sources: &[&str]) -> Result<Vec<u32>> {
+    let mut modules = Vec::new();
+    let result = (|| {
+        let sink = pactl(&["load-module", "module-null-sink", &format!("sink_name={}", MIX_SINK)])?;
+        modules.push(parse_module_id(&sink)?);
+        for source in sources {
+            let loopback = pactl(&[
+                "load-module",
+                "module-loopback",
+                &format!("source={}", source),
+                &format!("sink={}", MIX_SINK),
+                "latency_msec=20",
+            ])?;
+            modules.push(parse_module_id(&loopback)?);
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        unload_audio_modules(&modules);
+        return Err(e);
+    }
+    Ok(modules)
+}
+
+fn parse_module_id(output: &str) -> Result<u32> {
+    output.trim().parse()
+        .with_context(|| format!("Unexpected pactl load-module output: {}", output))
+}
+
+fn unload_audio_modules(modules: &[u32]) {
+    // Loopbacks go first so they never play into a missing sink.
+    for module in modules.iter().rev() {
+        let _ = pactl(&["unload-module", &module.to_string()]);
+    }
+}
+
+fn print_audio_sources(json: bool) -> Result<()> {
+    let sources = list_audio_sources()?;
+    if json {
+        println!("{}", serde_json::to_string(&sources)?);
+        return Ok(());
+    }
+    for source in sources {
+        let kind = if source.monitor { "system" } else { "input" };
+        let marker = if source.default { " (default)" } else { "" };
+        println!("{:<7} {}{}", kind, source.name, marker);
+    }
+    Ok(())
+}
+
 fn get_active_monitor() -> Result<String> {
-    let output = get_command_output(
-        &mut Command::new("hyprctl").args(["activewindow", "-j"])
-    )?;
-    let monitor = serde_json::from_str::<serde_json::Value>(&output)
-        .context("Failed to parse hyprctl output")?
-        .get("monitor")
-        .and_then(|m| m.as_i64())
-        .ok_or_else(|| anyhow!("Invalid monitor data"))?;
-
-    let output = get_command_output(
-        &mut Command::new("hyprctl").args(["monitors", "-j"])
-    )?;
-    let monitors = serde_json::from_str::<Vec<serde_json::Value>>(&output)
-        .context("Failed to parse monitors data")?;
-        
-    let monitor = monitors.get(monitor as usize)
-        .ok_or_else(|| anyhow!("Monitor not found"))?;
-        
-    monitor.get("name")
-        .and_then(|n| n.as_str())
-        .map(|s| s.to_string())
-        .ok_or_else(|| anyhow!("Invalid monitor name"))
+    let ipc = HyprlandIpc::from_env()?;
+    let monitors = ipc.monitors()?;
+
+    let monitor = match ipc.active_window()? {
+        Some(window) => monitors.into_iter().find(|m| m.id == window.monitor),
+        None => monitors.into_iter().find(|m| m.focused),
+    };
+
+    monitor
+        .map(|m| m.name)
+        .ok_or_else(|| anyhow!("Monitor not found"))
 }
 
 fn get_slurp_geometry() -> Result<String> {
@@ -83,44 +580,429 @@ fn get_slurp_geometry() -> Result<String> {
         .context("Failed to get selection geometry with slurp")
 }
 
-pub fn handle_recorder_command(args: &RecorderArgs, _debug: bool) -> Result<()> {
-    if is_process_running("wf-recorder") {
-        get_command_output(&mut Command::new("killall").args(&["-SIGINT", "wf-recorder"]))?;
-        Notification::new()
-            .summary("Recording stopped")
-            .appname("lunactl")
-            .show()?;
-    } else {
-        let dirs = xdg_user::UserDirs::new()?;
-        let videos_dir = dirs.videos()
-            .ok_or_else(|| anyhow!("Videos directory not found in XDG config"))?;
-        fs::create_dir_all(&videos_dir)?;
-        let date = Local::now().format("%Y%m%d_%H%M%S").to_string();
-        let output_file = videos_dir.join(format!("rec_{}.mp4", date));
-
-        Notification::new()
-            .summary("Recording started")
-            .body(output_file.to_str().unwrap_or("Invalid path"))
-            .appname("lunactl")
-            .show()
-            .map_err(|e| anyhow!("Failed to show notification: {}", e))?;
-
-        let mut cmd = Command::new("wf-recorder");
-        cmd.args(&["-c", "h264_vaapi", "-d", "/dev/dri/renderD128"]);
-        cmd.arg("-f").arg(&output_file);
-        cmd.arg("--audio").arg(get_audio_source()?);
-
-        match &args.command {
-            RecorderCommand::Toggle { fullscreen } => {
-                if *fullscreen {
-                    cmd.arg("-o").arg(get_active_monitor()?);
-                } else {
-                    cmd.arg("-g").arg(get_slurp_geometry()?);
+// --- Encoder Detection ---
+
+fn render_nodes() -> Vec<PathBuf> {
+    let mut nodes: Vec<PathBuf> = fs::read_dir("/dev/dri")
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("renderD")))
+                .collect()
+        })
+        .unwrap_or_default();
+    nodes.sort();
+    nodes
+}
+
+/// Asks vainfo whether `device` can encode `codec`. Returns `None` when
+/// vainfo is not installed.
+fn vaapi_supports(device: &Path, codec: Codec) -> Option<bool> {
+    let output = Command::new("vainfo")
+        .args(["--display", "drm", "--device"])
+        .arg(device)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return Some(false);
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Some(stdout.lines().any(|line| {
+        line.contains(codec.va_profile()) && line.contains("VAEntrypointEncSlice")
+    }))
+}
+
+fn resolve_encoder(args: &EncoderArgs) -> Result<EncoderPlan> {
+    let config: RecorderConfig = config::section("recorder")?;
+
+    let container = args.container.or(config.container).unwrap_or(Container::Mp4);
+    let codec = match args.codec.or(config.codec) {
+        Some(codec) => codec,
+        None if container == Container::Webm => Codec::Vp9,
+        None => Codec::H264,
+    };
+    if container == Container::Webm && !matches!(codec, Codec::Vp9 | Codec::Av1) {
+        bail!("WebM only supports the vp9 and av1 codecs");
+    }
+
+    let kind = args.encoder.or(config.encoder).unwrap_or(EncoderKind::Auto);
+    let candidates = match args.device.clone().or(config.device) {
+        Some(device) => vec![device],
+        None => render_nodes(),
+    };
+
+    let mut reason = String::from("software encoding requested");
+    let mut device = None;
+    if kind != EncoderKind::Software {
+        reason = "no render node found".to_string();
+        for node in candidates {
+            match vaapi_supports(&node, codec) {
+                Some(true) => {
+                    reason = format!("{} supports {:?} encoding", node.display(), codec);
+                    device = Some(node);
+                    break;
                 }
+                Some(false) => reason = format!("no render node supports {:?} encoding", codec),
+                // Without vainfo, trust the first render node as lunactl always has.
+                None => {
+                    reason = format!("vainfo not found, assuming {} supports VAAPI", node.display());
+                    device = Some(node);
+                    break;
+                }
+            }
+        }
+        if kind == EncoderKind::Vaapi && device.is_none() {
+            bail!("VAAPI encoding unavailable: {}", reason);
+        }
+    }
+
+    let encoder = match device {
+        Some(_) => codec.vaapi_encoder(),
+        None => codec.software_encoder(),
+    };
+
+    Ok(EncoderPlan {
+        codec,
+        encoder: encoder.to_string(),
+        device,
+        container,
+        framerate: args.framerate.or(config.framerate),
+        quality: args.quality.or(config.quality),
+        reason,
+    })
+}
+
+fn print_encoder_check(args: &EncoderArgs, json: bool) -> Result<()> {
+    let plan = resolve_encoder(args)?;
+    if json {
+        println!("{}", serde_json::to_string(&plan)?);
+        return Ok(());
+    }
+    println!("Encoder:   {}", plan.encoder);
+    println!("Device:    {}", plan.device.as_ref().map_or("none (software)".into(), |d| d.display().to_string()));
+    println!("Container: {}", plan.container.extension());
+    if let Some(framerate) = plan.framerate {
+        println!("Framerate: {}", framerate);
+    }
+    if let Some(quality) = plan.quality {
+        println!("Quality:   {}", quality);
+    }
+    println!("Reason:    {}", plan.reason);
+    Ok(())
+}
+
+// --- Clips ---
+
+const DEFAULT_CLIP_FPS: u32 = 15;
+const DEFAULT_CLIP_WIDTH: u32 = 720;
+
+fn resolve_clip(args: &StartArgs) -> Result<Option<ClipRequest>> {
+    let format = match (args.gif, args.webm_clip) {
+        (true, _) => ClipFormat::Gif,
+        (_, true) => ClipFormat::Webm,
+        _ => return Ok(None),
+    };
+    let config: RecorderConfig = config::section("recorder")?;
+    let fps = args.clip_fps.or(config.clip.fps).unwrap_or(DEFAULT_CLIP_FPS);
+    let max_width = args.clip_width.or(config.clip.max_width).unwrap_or(DEFAULT_CLIP_WIDTH);
+    if fps == 0 || max_width == 0 {
+        bail!("Clip framerate and width must be greater than zero");
+    }
+    Ok(Some(ClipRequest { format, fps, max_width }))
+}
+
+/// `<stem>.clip.<ext>` next to the recording, which never collides with
+/// the recording itself, even when both are WebM.
+fn clip_path(input: &Path, format: ClipFormat) -> PathBuf {
+    input.with_extension(format!("clip.{}", format.extension()))
+}
+
+/// Transcodes the recording with ffmpeg, next to the original file.
+fn convert_clip(input: &Path, clip: &ClipRequest) -> Result<PathBuf> {
+    let output = clip_path(input, clip.format);
+    // Never upscale, and keep the height even for the video encoders.
+    let scale = format!("fps={},scale='min({},iw)':-2:flags=lanczos", clip.fps, clip.max_width);
+
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-y", "-loglevel", "error", "-i"]).arg(input).arg("-an");
+    match clip.format {
+        ClipFormat::Gif => {
+            // A palette generated from the clip itself gives far better colors
+            // than the default 256-color one.
+            cmd.arg("-vf").arg(format!("{},split[a][b];[a]palettegen[p];[b][p]paletteuse", scale));
+        }
+        ClipFormat::Webm => {
+            cmd.arg("-vf").arg(scale);
+            cmd.args(["-c:v", "libvpx-vp9", "-b:v", "0", "-crf", "35", "-row-mt", "1"]);
+        }
+    }
+    cmd.arg(&output);
+
+    get_command_output(&mut cmd).context("Failed to convert recording with ffmpeg")?;
+    Ok(output)
+}
+
+fn copy_file_to_clipboard(path: &Path, mime_type: &str) -> Result<()> {
+    let file = fs::File::open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let status = Command::new("wl-copy")
+        .args(["--type", mime_type])
+        .stdin(file)
+        .status()
+        .context("Failed to execute wl-copy")?;
+    if !status.success() {
+        bail!("wl-copy failed to copy {}", path.display());
+    }
+    Ok(())
+}
+
+fn default_output_file(container: Container) -> Result<PathBuf> {
+    let dirs = xdg_user::UserDirs::new()?;
+    let videos_dir = dirs.videos()
+        .ok_or_else(|| anyhow!("Videos directory not found in XDG config"))?;
+    fs::create_dir_all(videos_dir)?;
+    let date = Local::now().format("%Y%m%d_%H%M%S").to_string();
+    Ok(videos_dir.join(format!("rec_{}.{}", date, container.extension())))
+}
+
+fn start_recording(args: &StartArgs) -> Result<()> {
+    if let Some(recording) = load_recording()? {
+        bail!("Already recording to {}", recording.output.display());
+    }
+
+    let plan = resolve_encoder(&args.encoder)?;
+    let clip = resolve_clip(args)?;
+    let output_file = default_output_file(plan.container)?;
+
+    let mut cmd = Command::new("wf-recorder");
+    plan.apply(&mut cmd);
+
+    // Pick the region first: cancelling slurp must not leave audio modules loaded.
+    if args.fullscreen {
+        cmd.arg("-o").arg(get_active_monitor()?);
+    } else {
+        cmd.arg("-g").arg(get_slurp_geometry()?);
+    }
+
+    let audio = resolve_audio_choice(args.audio.as_deref())?;
+    let (audio_source, audio_modules) = prepare_audio(&audio)?;
+    if let Some(source) = &audio_source {
+        cmd.arg(format!("--audio={}", source));
+    }
+    let recorder_args: Vec<String> = cmd.get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+    let pid = match spawn_recorder(&recorder_args, &output_file) {
+        Ok(pid) => pid,
+        Err(e) => {
+            unload_audio_modules(&audio_modules);
+            return Err(e);
+        }
+    };
+
+    state::save_json(&state_path()?, &RecordingState {
+        pid,
+        output: output_file.clone(),
+        started_at: Local::now(),
+        paused_at: None,
+        paused_secs: 0,
+        audio_modules,
+        clip,
+        recorder_args,
+        segments: Vec::new(),
+    })?;
+
+    // Only a recording that actually started makes the choice worth keeping.
+    if let Some(value) = &args.audio
+        && let Err(e) = remember_audio_choice(value)
+    {
+        eprintln!("Failed to remember the audio choice: {}", e);
+    }
+
+    Notification::new()
+        .summary("Recording started")
+        .body(&format!("{}\nAudio: {}", output_file.display(), audio.as_str()))
+        .appname("lunactl")
+        .show()
+        .map_err(|e| anyhow!("Failed to show notification: {}", e))?;
+    Ok(())
+}
+
+fn spawn_recorder(recorder_args: &[String], output: &Path) -> Result<u32> {
+    let child = Command::new("wf-recorder")
+        .args(recorder_args)
+        .arg("-f")
+        .arg(output)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to start wf-recorder")?;
+    Ok(child.id())
+}
+
+/// Stops wf-recorder with SIGINT and waits for it to finish writing the
+/// file. The state file is left alone if it does not exit, so the
+/// recording can still be stopped later.
+fn finish_segment(pid: u32) -> Result<()> {
+    send_signal(pid, "INT")?;
+    for _ in 0..50 {
+        if !is_recorder_alive(pid) {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    bail!("wf-recorder (pid {}) did not exit after SIGINT, try again or kill it", pid)
+}
+
+/// `rec_x.mp4` becomes `rec_x.part0.mp4`.
+fn segment_path(output: &Path, index: usize) -> PathBuf {
+    let extension = output.extension().map(|e| e.to_string_lossy().into_owned()).unwrap_or_default();
+    output.with_extension(format!("part{}.{}", index, extension))
+}
+
+/// Joins the segments of a paused recording into `output` without re-encoding.
+fn join_segments(segments: &[PathBuf], output: &Path) -> Result<()> {
+    let list = state::runtime_dir()?.join("recorder-segments.txt");
+    let content: String = segments
+        .iter()
+        .map(|s| format!("file '{}'\n", s.to_string_lossy().replace('\'', "'\\''")))
+        .collect();
+    fs::write(&list, content)?;
+    let result = get_command_output(
+        Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error", "-f", "concat", "-safe", "0", "-i"])
+            .arg(&list)
+            .args(["-c", "copy"])
+            .arg(output),
+    );
+    let _ = fs::remove_file(&list);
+    result.context("Failed to join recording segments with ffmpeg")?;
+    for segment in segments {
+        let _ = fs::remove_file(segment);
+    }
+    Ok(())
+}
+
+fn stop_recording() -> Result<()> {
+    let mut recording = load_recording()?
+        .ok_or_else(|| anyhow!("Not recording"))?;
+
+    let elapsed = recording.elapsed_secs();
+    if recording.paused_at.is_none() {
+        finish_segment(recording.pid)?;
+        if !recording.segments.is_empty() {
+            recording.segments.push(recording.current_file());
+        }
+    }
+    if !recording.segments.is_empty() {
+        join_segments(&recording.segments, &recording.output)?;
+    }
+    unload_audio_modules(&recording.audio_modules);
+    fs::remove_file(state_path()?)?;
+
+    match &recording.clip {
+        Some(clip) => {
+            let clip_file = convert_clip(&recording.output, clip)?;
+            copy_file_to_clipboard(&clip_file, clip.format.mime_type())?;
+            notify_stopped("Clip copied to clipboard", &clip_file, elapsed)
+        }
+        None => notify_stopped("Recording stopped", &recording.output, elapsed),
+    }
+}
+
+fn format_duration(secs: i64) -> String {
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Reports the file, its duration and size, and performs the action picked
+/// from the notification.
+fn notify_stopped(summary: &str, output: &Path, elapsed_secs: i64) -> Result<()> {
+    let name = output.file_name().map_or_else(|| output.to_string_lossy(), |n| n.to_string_lossy());
+    let size = fs::metadata(output).map(|m| notify::format_size(m.len())).unwrap_or_else(|_| "unknown size".to_string());
+    let body = format!("{}\n{} · {}", name, format_duration(elapsed_secs), size);
+    notify::notify_file(summary, &body, output, None)
+}
+
+/// Pausing ends the current segment and resuming starts a new one, so
+/// pauses leave no frozen frames in the joined video.
+fn toggle_pause() -> Result<()> {
+    let mut recording = load_recording()?
+        .ok_or_else(|| anyhow!("Not recording"))?;
+
+    match recording.paused_at {
+        Some(paused_at) => {
+            recording.pid = spawn_recorder(&recording.recorder_args, &recording.current_file())?;
+            recording.paused_secs += (Local::now() - paused_at).num_seconds();
+            recording.paused_at = None;
+            println!("Recording resumed.");
+        }
+        None => {
+            finish_segment(recording.pid)?;
+            let file = recording.current_file();
+            let segment = segment_path(&recording.output, recording.segments.len());
+            if file != segment {
+                fs::rename(&file, &segment)
+                    .with_context(|| format!("Failed to rename {}", file.display()))?;
             }
+            recording.segments.push(segment);
+            recording.paused_at = Some(Local::now());
+            println!("Recording paused.");
         }
+    }
+    state::save_json(&state_path()?, &recording)
+}
 
-        cmd.stdout(Stdio::null()).stderr(Stdio::null()).spawn()?;
+fn print_status(json: bool) -> Result<()> {
+    let recording = load_recording()?;
+    let status = RecorderStatus {
+        recording: recording.is_some(),
+        paused: recording.as_ref().is_some_and(|r| r.paused_at.is_some()),
+        pid: recording.as_ref().filter(|r| r.paused_at.is_none()).map(|r| r.pid),
+        output: recording.as_ref().map(|r| r.output.clone()),
+        started_at: recording.as_ref().map(|r| r.started_at),
+        elapsed_secs: recording.as_ref().map_or(0, |r| r.elapsed_secs()),
+    };
+
+    if json {
+        println!("{}", serde_json::to_string(&status)?);
+    } else if let Some(output) = &status.output {
+        let state = if status.paused { "Paused" } else { "Recording" };
+        println!("{} {} {}", state, format_duration(status.elapsed_secs), output.display());
+    } else {
+        println!("Not recording");
     }
     Ok(())
 }
+
+pub fn handle_recorder_command(args: &RecorderArgs, _debug: bool) -> Result<()> {
+    match &args.command {
+        RecorderCommand::Toggle(start_args) => {
+            if load_recording()?.is_some() {
+                stop_recording()
+            } else {
+                start_recording(start_args)
+            }
+        }
+        RecorderCommand::Start(start_args) => start_recording(start_args),
+        RecorderCommand::Stop => stop_recording(),
+        RecorderCommand::Status { json } => print_status(*json),
+        RecorderCommand::Pause => toggle_pause(),
+        RecorderCommand::Check { encoder, json } => print_encoder_check(encoder, *json),
+        RecorderCommand::Sources { json } => print_audio_sources(*json),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clip_path_differs_from_the_recording() {
+        let webm = Path::new("/videos/rec_20261018_120000.webm");
+        assert_eq!(clip_path(webm, ClipFormat::Webm), Path::new("/videos/rec_20261018_120000.clip.webm"));
+        assert_eq!(clip_path(webm, ClipFormat::Gif), Path::new("/videos/rec_20261018_120000.clip.gif"));
+
+        let mp4 = Path::new("/videos/demo.mp4");
+        assert_eq!(clip_path(mp4, ClipFormat::Webm), Path::new("/videos/demo.clip.webm"));
+        assert_ne!(clip_path(mp4, ClipFormat::Webm), mp4);
+    }
+}
@@ -1,16 +1,76 @@
-use anyhow::{Context, Result, anyhow};
-use clap::Args;
+use anyhow::{Context, Result, anyhow, bail};
+use clap::{Args, Subcommand};
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
 use std::process::Command;
 use std::str;
 
+use crate::config;
+use crate::hyprland_ipc::{HyprlandIpc, Monitor};
+
+/// Hyprland stores scales in 1/120 steps, as the fractional scale protocol does.
+const SCALE_STEPS: u32 = 120;
+const MIN_MONITOR_SCALE: f64 = 0.25;
+const MAX_MONITOR_SCALE: f64 = 4.0;
+
 #[derive(Args, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct ScaleArgs {
+    #[command(subcommand)]
+    pub command: Option<ScaleCommands>,
     /// The amount to adjust the text scaling factor by (e.g., 0.1 or -0.1)
-    pub adjustment: f64,
+    pub adjustment: Option<f64>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ScaleCommands {
+    /// Set the Hyprland output scale of a monitor
+    Monitor {
+        /// Monitor name (e.g. DP-1), or `focused`
+        name: String,
+        /// A scale such as 1.5, or a change such as +0.25 or -0.25
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+        /// Also write the scale to hypr/hyprland/monitors/default.conf
+        #[arg(long)]
+        persist: bool,
+    },
+    /// Print the text scaling factor and the monitor scales
+    Get {
+        /// Print the scales as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Serialize, Debug)]
+struct ScaleReport {
+    text: f64,
+    monitors: Vec<MonitorScale>,
+}
+
+#[derive(Serialize, Debug)]
+struct MonitorScale {
+    name: String,
+    width: i32,
+    height: i32,
+    scale: f64,
+    focused: bool,
 }
 
 pub fn handle_scale_command(args: &ScaleArgs, _debug: bool) -> Result<()> {
-    // Get the current scaling factor
+    match (&args.command, args.adjustment) {
+        (Some(ScaleCommands::Monitor { name, value, persist }), _) => handle_monitor(name, value, *persist),
+        (Some(ScaleCommands::Get { json }), _) => handle_get(*json),
+        (None, Some(adjustment)) => adjust_text_scale(adjustment),
+        (None, None) => bail!("Expected a text scale adjustment or a subcommand, see `lunactl scale --help`"),
+    }
+}
+
+// --- Text Scaling ---
+
+fn text_scale() -> Result<f64> {
     let output = Command::new("gsettings")
         .args(["get", "org.gnome.desktop.interface", "text-scaling-factor"])
         .output()
@@ -26,12 +86,14 @@ pub fn handle_scale_command(args: &ScaleArgs, _debug: bool) -> Result<()> {
         .context("Invalid UTF-8 in gsettings output")?
         .trim();
 
-    let current: f64 = current_str
+    current_str
         .parse()
-        .with_context(|| format!("Failed to parse scale value: {}", current_str))?;
+        .with_context(|| format!("Failed to parse scale value: {}", current_str))
+}
 
+fn adjust_text_scale(adjustment: f64) -> Result<()> {
     // Calculate the new scaling factor
-    let mut new_scale = current + args.adjustment;
+    let mut new_scale = text_scale()? + adjustment;
 
     // Ensure the new scaling factor is not less than a minimum
     const MIN_SCALE: f64 = 0.1;
@@ -51,3 +113,307 @@ pub fn handle_scale_command(args: &ScaleArgs, _debug: bool) -> Result<()> {
 
     Ok(())
 }
+
+// --- Monitor Scaling ---
+
+fn find_monitor<'a>(monitors: &'a [Monitor], name: &str) -> Result<&'a Monitor> {
+    if name == "focused" {
+        return monitors.iter().find(|m| m.focused).context("No focused monitor");
+    }
+    monitors.iter()
+        .find(|m| m.name == name)
+        .ok_or_else(|| anyhow!("No monitor named {}", name))
+}
+
+/// Scales that give a whole number of logical pixels in both directions,
+/// which is what Hyprland requires before it accepts a scale.
+fn valid_scales(width: i32, height: i32) -> Vec<f64> {
+    let (width, height) = (width.max(1) as u32 * SCALE_STEPS, height.max(1) as u32 * SCALE_STEPS);
+    let first = (MIN_MONITOR_SCALE * SCALE_STEPS as f64) as u32;
+    let last = (MAX_MONITOR_SCALE * SCALE_STEPS as f64) as u32;
+    (first..=last)
+        .filter(|step| width.is_multiple_of(*step) && height.is_multiple_of(*step))
+        .map(|step| step as f64 / SCALE_STEPS as f64)
+        .collect()
+}
+
+/// Picks the valid scale closest to `target`. A relative change always moves
+/// at least one valid scale, so small steps are not swallowed by snapping.
+fn snap_scale(monitor: &Monitor, target: f64, relative: bool) -> f64 {
+    let scales = valid_scales(monitor.width, monitor.height);
+    let closest = |target: f64| {
+        scales.iter().copied()
+            .min_by(|a, b| (a - target).abs().total_cmp(&(b - target).abs()))
+            .unwrap_or(1.0)
+    };
+    let snapped = closest(target);
+    let unchanged = (snapped - monitor.scale).abs() < 1e-6;
+    if !relative || !unchanged {
+        return snapped;
+    }
+    if target > monitor.scale {
+        scales.iter().copied().find(|s| *s > monitor.scale + 1e-6).unwrap_or(snapped)
+    } else if target < monitor.scale {
+        scales.iter().rev().copied().find(|s| *s < monitor.scale - 1e-6).unwrap_or(snapped)
+    } else {
+        snapped
+    }
+}
+
+/// Formats a scale without float noise, e.g. `1.333333` rather than `1.3333333333333333`.
+fn format_scale(scale: f64) -> String {
+    let formatted = format!("{:.6}", scale);
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn handle_monitor(name: &str, value: &str, persist: bool) -> Result<()> {
+    let relative = value.starts_with('+') || value.starts_with('-');
+    let number: f64 = value
+        .parse()
+        .with_context(|| format!("Invalid scale: {}", value))?;
+    if !number.is_finite() || (!relative && number <= 0.0) {
+        bail!("Invalid scale: {}", value);
+    }
+
+    let ipc = HyprlandIpc::from_env()?;
+    let monitors = ipc.monitors()?;
+    let monitor = find_monitor(&monitors, name)?;
+    let target = if relative { monitor.scale + number } else { number };
+    let scale = format_scale(snap_scale(monitor, target, relative));
+
+    let rule = monitor_rule(monitor, &scale, &monitors);
+    ipc.keyword("monitor", &rule)?;
+    println!("{}: {}", monitor.name, scale);
+
+    if persist {
+        let path = persist_monitor_scale(&monitor.name, &scale)?;
+        println!("Saved to {}", path.display());
+    }
+    Ok(())
+}
+
+/// A `monitor` rule that keeps the current mode, position and extra settings
+/// and only changes the scale. Hyprland reports whether VRR is active, not
+/// the configured mode, so a fullscreen-only `vrr, 2` is left to the global
+/// `misc:vrr` until the next config reload.
+fn monitor_rule(monitor: &Monitor, scale: &str, monitors: &[Monitor]) -> String {
+    let mut rule = format!(
+        "{},{}x{}@{:.2},{}x{},{}",
+        monitor.name, monitor.width, monitor.height, monitor.refresh_rate, monitor.x, monitor.y, scale
+    );
+    if monitor.transform != 0 {
+        rule.push_str(&format!(",transform,{}", monitor.transform));
+    }
+    if let Some(mirrored) = monitors.iter().find(|m| m.id.to_string() == monitor.mirror_of) {
+        rule.push_str(&format!(",mirror,{}", mirrored.name));
+    }
+    if monitor.current_format.contains("2101010") {
+        rule.push_str(",bitdepth,10");
+    }
+    if monitor.vrr {
+        rule.push_str(",vrr,1");
+    }
+    if let Some(preset) = monitor.color_management_preset.as_deref().filter(|p| *p != "srgb") {
+        rule.push_str(&format!(",cm,{}", preset));
+        if let Some(brightness) = monitor.sdr_brightness.filter(|b| *b != 1.0) {
+            rule.push_str(&format!(",sdrbrightness,{}", format_scale(brightness)));
+        }
+        if let Some(saturation) = monitor.sdr_saturation.filter(|s| *s != 1.0) {
+            rule.push_str(&format!(",sdrsaturation,{}", format_scale(saturation)));
+        }
+    }
+    rule
+}
+
+/// Splits a `monitor = name, mode, position, scale, ...` line into its
+/// fields and any trailing comment.
+fn parse_monitor_line(line: &str) -> Option<(Vec<String>, &str)> {
+    let (rule, comment) = match line.find('#') {
+        Some(index) => line.split_at(index),
+        None => (line, ""),
+    };
+    let value = rule.trim().strip_prefix("monitor")?.trim_start().strip_prefix('=')?;
+    let fields: Vec<String> = value.split(',').map(|f| f.trim().to_string()).collect();
+    // `monitor = name, disable` has no scale to set.
+    (fields.len() >= 3).then_some((fields, comment))
+}
+
+/// Writes the scale to hypr/hyprland/monitors/default.conf.
+fn persist_monitor_scale(name: &str, scale: &str) -> Result<PathBuf> {
+    let path = config::hypr_config_dir().join("hyprland/monitors/default.conf");
+    let content = fs::read_to_string(&path).unwrap_or_default();
+    fs::write(&path, set_monitor_scale_in(&content, name, scale))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Sets the scale in the monitor's rule, adding a rule based on the
+/// catch-all `monitor = , ...` one if there is none yet.
+fn set_monitor_scale_in(content: &str, name: &str, scale: &str) -> String {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let mut defaults = None;
+    let mut found = false;
+    for line in &mut lines {
+        let Some((mut fields, comment)) = parse_monitor_line(line) else {
+            continue;
+        };
+        if fields[0] == name {
+            fields.resize(fields.len().max(4), String::new());
+            fields[3] = scale.to_string();
+            let separator = if comment.is_empty() { "" } else { " " };
+            *line = format!("monitor = {}{}{}", fields.join(", "), separator, comment);
+            found = true;
+        } else if fields[0].is_empty() {
+            defaults = Some((fields[1].clone(), fields[2].clone()));
+        }
+    }
+    if !found {
+        let (mode, position) = defaults.unwrap_or(("preferred".to_string(), "auto".to_string()));
+        lines.push(format!("monitor = {}, {}, {}, {}", name, mode, position, scale));
+    }
+    lines.join("\n") + "\n"
+}
+
+// --- Reporting ---
+
+fn handle_get(json: bool) -> Result<()> {
+    let monitors = HyprlandIpc::from_env()?
+        .monitors()?
+        .into_iter()
+        .map(|m| MonitorScale {
+            name: m.name,
+            width: m.width,
+            height: m.height,
+            scale: m.scale,
+            focused: m.focused,
+        })
+        .collect();
+    let report = ScaleReport { text: text_scale()?, monitors };
+
+    if json {
+        println!("{}", serde_json::to_string(&report)?);
+        return Ok(());
+    }
+    println!("text: {}", format_scale(report.text));
+    for monitor in report.monitors {
+        let focused = if monitor.focused { " (focused)" } else { "" };
+        println!(
+            "{}: {} at {}x{}{}",
+            monitor.name, format_scale(monitor.scale), monitor.width, monitor.height, focused
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(id: i32, name: &str, extra: serde_json::Value) -> Monitor {
+        let mut value = serde_json::json!({
+            "id": id, "name": name, "width": 2560, "height": 1440, "refreshRate": 143.998,
+            "x": 1920, "y": 0, "activeWorkspace": {"id": 1, "name": "1"}, "scale": 1.0, "focused": true,
+        });
+        value.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn monitor_rule_keeps_mode_and_position() {
+        let dp = monitor(0, "DP-1", serde_json::json!({ "currentFormat": "XRGB8888", "colorManagementPreset": "srgb" }));
+        assert_eq!(monitor_rule(&dp, "1.25", &[]), "DP-1,2560x1440@144.00,1920x0,1.25");
+    }
+
+    #[test]
+    fn monitor_rule_carries_extra_settings() {
+        let hdmi = monitor(0, "HDMI-A-1", serde_json::json!({}));
+        let dp = monitor(1, "DP-1", serde_json::json!({
+            "transform": 1, "mirrorOf": "0", "vrr": true, "currentFormat": "XRGB2101010",
+            "colorManagementPreset": "hdr", "sdrBrightness": 1.2, "sdrSaturation": 1.0,
+        }));
+        assert_eq!(
+            monitor_rule(&dp, "1.5", &[hdmi, dp.clone()]),
+            "DP-1,2560x1440@144.00,1920x0,1.5,transform,1,mirror,HDMI-A-1,bitdepth,10,vrr,1,cm,hdr,sdrbrightness,1.2"
+        );
+    }
+
+    fn formatted(scales: &[f64]) -> Vec<String> {
+        scales.iter().map(|s| format_scale(*s)).collect()
+    }
+
+    #[test]
+    fn valid_scales_divide_both_dimensions() {
+        let full_hd = formatted(&valid_scales(1920, 1080));
+        for scale in ["0.25", "0.5", "1", "1.2", "1.25", "1.333333", "1.5", "1.6", "1.875", "2", "2.5", "3", "4"] {
+            assert!(full_hd.contains(&scale.to_string()), "{} is missing at 1920x1080", scale);
+        }
+        assert!(!full_hd.contains(&"1.75".to_string()));
+
+        let qhd = formatted(&valid_scales(2560, 1440));
+        for scale in ["1", "1.066667", "1.25", "1.333333", "1.6", "2", "2.5", "3.2", "4"] {
+            assert!(qhd.contains(&scale.to_string()), "{} is missing at 2560x1440", scale);
+        }
+        for scale in ["1.2", "1.5", "1.75", "3"] {
+            assert!(!qhd.contains(&scale.to_string()), "{} is not valid at 2560x1440", scale);
+        }
+
+        for scale in valid_scales(2560, 1440) {
+            let (width, height) = (2560.0 / scale, 1440.0 / scale);
+            assert!((width - width.round()).abs() < 1e-9 && (height - height.round()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn snap_scale_picks_the_closest_valid_scale() {
+        let qhd = monitor(0, "DP-1", serde_json::json!({ "scale": 1.25 }));
+        assert_eq!(format_scale(snap_scale(&qhd, 1.5, false)), "1.6");
+        assert_eq!(format_scale(snap_scale(&qhd, 1.3, false)), "1.333333");
+        assert_eq!(format_scale(snap_scale(&qhd, 1.26, false)), "1.25");
+        assert_eq!(format_scale(snap_scale(&qhd, 9.0, false)), "4");
+
+        let full_hd = monitor(0, "eDP-1", serde_json::json!({ "width": 1920, "height": 1080 }));
+        assert_eq!(format_scale(snap_scale(&full_hd, 1.5, false)), "1.5");
+        assert_eq!(format_scale(snap_scale(&full_hd, 1.74, false)), "1.666667");
+    }
+
+    #[test]
+    fn relative_snap_moves_at_least_one_step() {
+        let qhd = monitor(0, "DP-1", serde_json::json!({ "scale": 1.25 }));
+        assert_eq!(format_scale(snap_scale(&qhd, 1.26, true)), "1.333333");
+        assert_eq!(format_scale(snap_scale(&qhd, 1.24, true)), "1.066667");
+        assert_eq!(format_scale(snap_scale(&qhd, 1.5, true)), "1.6");
+
+        let full_hd = monitor(0, "eDP-1", serde_json::json!({ "width": 1920, "height": 1080 }));
+        assert_eq!(format_scale(snap_scale(&full_hd, 1.05, true)), "1.2");
+        assert_eq!(format_scale(snap_scale(&full_hd, 0.95, true)), "0.833333");
+
+        let smallest = monitor(0, "eDP-1", serde_json::json!({ "scale": 0.25 }));
+        assert_eq!(format_scale(snap_scale(&smallest, 0.2, true)), "0.25");
+    }
+
+    #[test]
+    fn persist_rewrites_the_monitor_line() {
+        let content = "monitor = , preferred, auto, 1\nmonitor = DP-1, 2560x1440@144, 1920x0, 1 # desk\nmonitor = HDMI-A-1, disable\n";
+        assert_eq!(
+            set_monitor_scale_in(content, "DP-1", "1.25"),
+            "monitor = , preferred, auto, 1\nmonitor = DP-1, 2560x1440@144, 1920x0, 1.25 # desk\nmonitor = HDMI-A-1, disable\n"
+        );
+
+        let without_scale = "monitor=eDP-1,1920x1080@60,0x0\n";
+        assert_eq!(set_monitor_scale_in(without_scale, "eDP-1", "1.5"), "monitor = eDP-1, 1920x1080@60, 0x0, 1.5\n");
+    }
+
+    #[test]
+    fn persist_ignores_commented_lines() {
+        let content = "# monitor = DP-1, 2560x1440@144, 0x0, 2\nmonitor = , highres, auto, 1";
+        assert_eq!(
+            set_monitor_scale_in(content, "DP-1", "1.6"),
+            "# monitor = DP-1, 2560x1440@144, 0x0, 2\nmonitor = , highres, auto, 1\nmonitor = DP-1, highres, auto, 1.6\n"
+        );
+    }
+
+    #[test]
+    fn persist_falls_back_to_preferred_without_a_catch_all() {
+        assert_eq!(set_monitor_scale_in("", "DP-1", "2"), "monitor = DP-1, preferred, auto, 2\n");
+    }
+}
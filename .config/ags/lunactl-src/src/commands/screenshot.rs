@@ -1,9 +1,28 @@
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Local};
 use clap::{Args, Subcommand};
-use std::{env, fs};
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
-use std::time::{SystemTime, UNIX_EPOCH};
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
+use std::{env, fs, thread};
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config;
+use crate::image_effects;
+use crate::notify;
+use crate::hyprland_ipc::{HyprlandIpc, Monitor};
+use crate::screencopy::{self, Rect};
+use crate::state;
+
+/// The `apps` section of config.jsonc.
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct AppsConfig {
+    image_viewer: Option<String>,
+}
 
 #[derive(Args, Debug)]
 pub struct ScreenshotArgs {
@@ -24,6 +43,21 @@ pub struct ScreenshotArgs {
 
     #[arg(long, short, help = "Scale the screenshot")]
     scale: Option<f64>,
+
+    #[arg(long, value_name = "PX", help = "Round the corners by N pixels")]
+    round: Option<u32>,
+
+    #[arg(long, help = "Add a drop shadow")]
+    shadow: bool,
+
+    #[arg(long, value_name = "PX", help = "Pad the screenshot by N pixels")]
+    pad: Option<u32>,
+
+    #[arg(long, requires = "pad", help = "Padding color (#rrggbb, #rrggbbaa or transparent)")]
+    bg: Option<String>,
+
+    #[arg(long, help = "Capture with grim instead of wlr-screencopy")]
+    grim: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -43,7 +77,38 @@ pub enum ScreenshotAction {
         path: Option<String> 
     },
     /// Open screenshot in an editor
-    Edit { #[command(subcommand)] target: ScreenshotTarget },
+    Edit {
+        #[command(subcommand)]
+        target: ScreenshotTarget,
+        /// Editor command [default: config.jsonc apps.imageViewer]
+        #[arg(long)]
+        editor: Option<String>,
+    },
+    /// Extract text with tesseract and copy it to the clipboard
+    Ocr {
+        #[command(subcommand)]
+        target: ScreenshotTarget,
+        /// Tesseract language(s), e.g. eng or eng+deu
+        #[arg(long, default_value = "eng")]
+        lang: String,
+    },
+    /// Decode QR codes and copy their contents to the clipboard (1D barcodes are not supported)
+    Qr { #[command(subcommand)] target: ScreenshotTarget },
+    /// List recent screenshots
+    List {
+        /// Number of screenshots to show
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+        /// Print the entries as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Copy the most recent screenshot to the clipboard again
+    Last,
+    /// Open a recent screenshot (1 is the most recent)
+    Open { #[arg(default_value_t = 1)] index: usize },
+    /// Delete a recent screenshot (1 is the most recent)
+    Delete { #[arg(default_value_t = 1)] index: usize },
     /// Check for dependencies
     Check,
 }
@@ -60,6 +125,17 @@ pub enum ScreenshotTarget {
     Area,
 }
 
+impl ScreenshotTarget {
+    fn kind(&self) -> &'static str {
+        match self {
+            ScreenshotTarget::Active => "active",
+            ScreenshotTarget::Screen => "screen",
+            ScreenshotTarget::Output => "output",
+            ScreenshotTarget::Area => "area",
+        }
+    }
+}
+
 
 
 // --- Helper Functions ---
@@ -95,36 +171,120 @@ fn get_target_directory() -> Result<PathBuf> {
     Ok(pictures_dir)
 }
 
-fn generate_filename() -> String {
+/// A new file in `dir` named after the current time in milliseconds, with a
+/// `-N` suffix if a capture in the same millisecond already took the name.
+fn generate_file_path(dir: &Path) -> PathBuf {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
-        .as_secs();
-    format!("screenshot_{}.png", now)
+        .as_millis();
+    unused_path(dir, &format!("screenshot_{}", now), "png")
 }
 
-fn get_active_window_geom() -> Result<String> {
-    let output = Command::new("hyprctl")
-        .args(["-j", "activewindow"])
-        .output()
-        .context("Failed to execute hyprctl")?;
+fn unused_path(dir: &Path, stem: &str, extension: &str) -> PathBuf {
+    let mut path = dir.join(format!("{}.{}", stem, extension));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{}-{}.{}", stem, n, extension));
+        n += 1;
+    }
+    path
+}
 
-    if !output.status.success() {
-        return Err(anyhow!("Failed to get active window geometry"));
+/// A rectangle in global logical coordinates, as printed by slurp.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+struct Geometry {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl Geometry {
+    /// Parses slurp's `X,Y WxH` format.
+    fn parse(geom: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid geometry: {}", geom);
+        let (position, size) = geom.trim().split_once(' ').ok_or_else(invalid)?;
+        let (x, y) = position.split_once(',').ok_or_else(invalid)?;
+        let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+        Ok(Geometry {
+            x: x.parse().map_err(|_| invalid())?,
+            y: y.parse().map_err(|_| invalid())?,
+            width: width.parse().map_err(|_| invalid())?,
+            height: height.parse().map_err(|_| invalid())?,
+        })
     }
 
-    let window: serde_json::Value = serde_json::from_slice(&output.stdout)
-        .context("Failed to parse hyprctl output")?;
+    fn to_grim(self) -> String {
+        format!("{},{} {}x{}", self.x, self.y, self.width, self.height)
+    }
 
-    let x = window["at"][0].as_i64().context("Failed to get x coordinate")?;
-    let y = window["at"][1].as_i64().context("Failed to get y coordinate")?;
-    let width = window["size"][0].as_i64().context("Failed to get width")?;
-    let height = window["size"][1].as_i64().context("Failed to get height")?;
+    /// Returns the geometry relative to `monitor` if it lies entirely on it.
+    fn local_to(self, monitor: &Monitor) -> Option<Rect> {
+        let (mw, mh) = monitor_logical_size(monitor);
+        let (x, y) = (self.x - monitor.x, self.y - monitor.y);
+        (x >= 0 && y >= 0 && x + self.width <= mw && y + self.height <= mh)
+            .then_some(Rect { x, y, width: self.width, height: self.height })
+    }
 
-    Ok(format!("{},{},{},{}", x, y, width, height))
+    /// Whether any part of the geometry is on `monitor`.
+    fn overlaps(self, monitor: &Monitor) -> bool {
+        let (mw, mh) = monitor_logical_size(monitor);
+        self.x < monitor.x + mw && monitor.x < self.x + self.width
+            && self.y < monitor.y + mh && monitor.y < self.y + self.height
+    }
+}
+
+/// Hyprland reports monitor sizes in physical pixels.
+fn monitor_logical_size(monitor: &Monitor) -> (i32, i32) {
+    let (width, height) = if monitor.transform % 2 == 1 {
+        (monitor.height, monitor.width)
+    } else {
+        (monitor.width, monitor.height)
+    };
+    (
+        (width as f64 / monitor.scale).round() as i32,
+        (height as f64 / monitor.scale).round() as i32,
+    )
+}
+
+/// What a target resolves to before capturing.
+enum CaptureSource {
+    Output(Box<Monitor>),
+    Region(Geometry),
+    Screen,
+}
+
+impl CaptureSource {
+    fn geometry(&self) -> Option<Geometry> {
+        match self {
+            CaptureSource::Output(monitor) => {
+                let (width, height) = monitor_logical_size(monitor);
+                Some(Geometry { x: monitor.x, y: monitor.y, width, height })
+            }
+            CaptureSource::Region(geom) => Some(*geom),
+            CaptureSource::Screen => None,
+        }
+    }
+}
+
+/// A captured image and the number of image pixels per logical pixel.
+struct Capture {
+    image: RgbaImage,
+    pixel_ratio: f64,
+}
+
+fn get_active_window_geom(ipc: &HyprlandIpc) -> Result<Geometry> {
+    let window = ipc.active_window()?
+        .ok_or_else(|| anyhow!("No active window"))?;
+
+    let [x, y] = window.at;
+    let [width, height] = window.size;
+
+    Ok(Geometry { x, y, width, height })
 }
 
-fn get_area_geom() -> Result<String> {
+fn get_area_geom() -> Result<Geometry> {
     let output = Command::new("slurp")
         .output()
         .context("Failed to execute slurp")?;
@@ -141,32 +301,365 @@ fn get_area_geom() -> Result<String> {
         return Err(anyhow!("No area selected"));
     }
 
-    Ok(geom)
+    Geometry::parse(&geom)
 }
 
-fn get_active_output_name() -> Result<String> {
-    let output = Command::new("hyprctl")
-        .args(["-j", "monitors"])
-        .output()
-        .context("Failed to execute hyprctl")?;
+fn resolve_source(target: &ScreenshotTarget, ipc: &HyprlandIpc) -> Result<CaptureSource> {
+    Ok(match target {
+        ScreenshotTarget::Active => CaptureSource::Region(get_active_window_geom(ipc)?),
+        ScreenshotTarget::Area => CaptureSource::Region(get_area_geom()?),
+        ScreenshotTarget::Output => CaptureSource::Output(Box::new(ipc.focused_monitor()?)),
+        ScreenshotTarget::Screen => CaptureSource::Screen,
+    })
+}
 
+fn capture_with_screencopy(source: &CaptureSource, monitors: &[Monitor], cursor: bool) -> Result<Capture> {
+    let (monitor, region) = match source {
+        CaptureSource::Output(monitor) => (&**monitor, None),
+        CaptureSource::Region(geom) => monitors
+            .iter()
+            .find_map(|m| geom.local_to(m).map(|rect| (m, Some(rect))))
+            .ok_or_else(|| anyhow!("Region spans more than one output"))?,
+        CaptureSource::Screen => match monitors {
+            [monitor] => (monitor, None),
+            _ => bail!("Capturing several outputs at once is not supported by screencopy"),
+        },
+    };
+    let image = screencopy::capture_output(&monitor.name, region, cursor)?;
+    Ok(Capture { image, pixel_ratio: monitor.scale })
+}
+
+fn capture_with_grim(source: &CaptureSource, monitors: &[Monitor], cursor: bool) -> Result<Capture> {
+    let mut grim_command = Command::new("grim");
+    if cursor {
+        grim_command.arg("-c");
+    }
+    match source {
+        CaptureSource::Output(monitor) => grim_command.arg("-o").arg(&monitor.name),
+        CaptureSource::Region(geom) => grim_command.arg("-g").arg(geom.to_grim()),
+        CaptureSource::Screen => &mut grim_command,
+    };
+    let output = grim_command
+        .arg("-")
+        .stderr(Stdio::inherit())
+        .output()
+        .context("Failed to execute grim")?;
     if !output.status.success() {
-        return Err(anyhow!("Failed to get monitors"));
+        return Err(anyhow!("grim failed with status: {}", output.status));
+    }
+
+    let image = image::load_from_memory(&output.stdout)
+        .context("Failed to decode grim output")?
+        .into_rgba8();
+    // grim renders an output at its own scale, and anything else at the
+    // greatest scale of the outputs it covers.
+    let pixel_ratio = match source {
+        CaptureSource::Output(monitor) => monitor.scale,
+        CaptureSource::Region(geom) => monitors.iter()
+            .filter(|m| geom.overlaps(m))
+            .map(|m| m.scale)
+            .fold(1.0, f64::max),
+        CaptureSource::Screen => monitors.iter().map(|m| m.scale).fold(1.0, f64::max),
+    };
+    Ok(Capture { image, pixel_ratio })
+}
+
+/// Captures in-process via wlr-screencopy, falling back to grim when the
+/// compositor or the requested geometry is not supported.
+fn capture(source: &CaptureSource, monitors: &[Monitor], args: &ScreenshotArgs, debug: bool) -> Result<Capture> {
+    if !args.grim {
+        match capture_with_screencopy(source, monitors, args.cursor) {
+            Ok(capture) => return Ok(capture),
+            Err(e) if debug => eprintln!("Screencopy failed ({}), falling back to grim", e),
+            Err(_) => {}
+        }
+    }
+    capture_with_grim(source, monitors, args.cursor)
+}
+
+fn image_format_for(path: &Path) -> ImageFormat {
+    path.extension()
+        .and_then(ImageFormat::from_extension)
+        .filter(|f| matches!(f, ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP))
+        .unwrap_or(ImageFormat::Png)
+}
+
+fn encode(image: &RgbaImage, format: ImageFormat) -> Result<Vec<u8>> {
+    let mut bytes = Cursor::new(Vec::new());
+    // JPEG has no alpha channel.
+    if format == ImageFormat::Jpeg {
+        DynamicImage::ImageRgba8(image.clone()).into_rgb8().write_to(&mut bytes, format)?;
+    } else {
+        image.write_to(&mut bytes, format)?;
+    }
+    Ok(bytes.into_inner())
+}
+
+fn copy_to_clipboard(bytes: &[u8], mime_type: &str) -> Result<()> {
+    let mut wl_copy_process = Command::new("wl-copy")
+        .args(["--type", mime_type])
+        .stdin(Stdio::piped())
+        .spawn()
+        .context("Failed to execute wl-copy")?;
+    if let Some(mut stdin) = wl_copy_process.stdin.take() {
+        stdin.write_all(bytes)?;
+    }
+    wl_copy_process.wait()?;
+    Ok(())
+}
+
+fn save_to_file(image: &RgbaImage, file_path: &Path) -> Result<()> {
+    if let Some(parent) = file_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(file_path, encode(image, image_format_for(file_path))?)
+        .with_context(|| format!("Failed to write {}", file_path.display()))
+}
+
+/// Keeps the screen frozen with hyprpicker until dropped.
+struct ScreenFreeze(Child);
+
+impl ScreenFreeze {
+    fn start() -> Result<Self> {
+        let child = Command::new("hyprpicker")
+            .args(["-r", "-z"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Failed to start hyprpicker to freeze the screen")?;
+        // Give hyprpicker a moment to cover the outputs before selecting.
+        thread::sleep(Duration::from_millis(200));
+        Ok(ScreenFreeze(child))
+    }
+}
+
+impl Drop for ScreenFreeze {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Splits the editor command from `--editor` or config.jsonc into program and arguments.
+fn resolve_editor(editor: &Option<String>) -> Result<Vec<String>> {
+    let editor = match editor {
+        Some(editor) => editor.clone(),
+        None => config::section::<AppsConfig>("apps")?
+            .image_viewer
+            .unwrap_or_else(|| "swappy".to_string()),
+    };
+    let parts: Vec<String> = editor.split_whitespace().map(str::to_string).collect();
+    if parts.is_empty() {
+        return Err(anyhow!("No screenshot editor configured"));
+    }
+    Ok(parts)
+}
+
+/// Arguments that make known editors read the image from stdin.
+fn editor_stdin_args(program: &str) -> Option<&'static [&'static str]> {
+    let name = Path::new(program).file_name()?.to_str()?;
+    match name {
+        "swappy" => Some(&["-f", "-"]),
+        "satty" => Some(&["--filename", "-"]),
+        _ => None,
+    }
+}
+
+fn send_notification(summary: &str, file_path: Option<&Path>) -> Result<()> {
+    match file_path {
+        Some(path) => notify::notify_file(summary, &path.to_string_lossy(), path, Some(path)),
+        None => send_text_notification(summary, None, None),
     }
+}
 
-    let monitors: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout)
-        .context("Failed to parse hyprctl output")?;
+fn send_text_notification(summary: &str, body: Option<&str>, icon: Option<&str>) -> Result<()> {
+    let mut notification = Notification::new();
+    notification.summary(summary).appname("lunactl");
+    if let Some(body) = body {
+        notification.body(body);
+    }
+    if let Some(icon) = icon {
+        notification.icon(icon);
+    }
+    notification
+        .show()
+        .map_err(|e| anyhow!("Failed to show notification: {}", e))?;
+    Ok(())
+}
 
-    for monitor in monitors {
-        if monitor["focused"].as_bool().unwrap_or(false) {
-            return monitor["name"]
-                .as_str()
-                .map(|s| s.to_string())
-                .ok_or_else(|| anyhow!("Failed to get monitor name"));
+/// Applies scaling, rounded corners, shadow and padding, in that order.
+/// Sizes given on the command line are in logical pixels.
+fn post_process(capture: Capture, args: &ScreenshotArgs) -> Result<RgbaImage> {
+    let Capture { mut image, pixel_ratio } = capture;
+    let background = args.bg.as_deref().map(image_effects::parse_color).transpose()?;
+
+    let ratio = match args.scale {
+        Some(scale) => {
+            image = image_effects::scale(&image, scale / pixel_ratio);
+            scale
         }
+        None => pixel_ratio,
+    };
+    if let Some(radius) = args.round {
+        image_effects::round_corners(&mut image, radius as f64 * ratio);
+    }
+    if args.shadow {
+        image = image_effects::add_shadow(&image, ratio);
     }
+    if let Some(amount) = args.pad {
+        let amount = (amount as f64 * ratio).round() as u32;
+        image = image_effects::pad(&image, amount, background.unwrap_or(Rgba([0, 0, 0, 0])));
+    }
+    Ok(image)
+}
 
-    Err(anyhow!("No active monitor found"))
+// --- Text Extraction ---
+
+fn recognize_text(image: &RgbaImage, lang: &str) -> Result<String> {
+    let mut tesseract = Command::new("tesseract")
+        .args(["stdin", "stdout", "-l", lang])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to execute tesseract, is it installed?")?;
+    if let Some(mut stdin) = tesseract.stdin.take() {
+        stdin.write_all(&encode(image, ImageFormat::Png)?)?;
+    }
+    let output = tesseract.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!("tesseract failed with status: {}", output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Decodes every QR code found in the image.
+fn decode_qr_codes(image: &RgbaImage) -> Vec<String> {
+    let luma = DynamicImage::ImageRgba8(image.clone()).into_luma8();
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        luma.width() as usize,
+        luma.height() as usize,
+        |x, y| luma.get_pixel(x as u32, y as u32)[0],
+    );
+    prepared
+        .detect_grids()
+        .into_iter()
+        .filter_map(|grid| grid.decode().ok().map(|(_, content)| content))
+        .collect()
+}
+
+fn copy_text_result(summary: &str, text: &str, notify: bool) -> Result<()> {
+    copy_to_clipboard(text.as_bytes(), "text/plain;charset=utf-8")?;
+    println!("{}", text);
+    if notify {
+        send_text_notification(summary, Some(text), None)?;
+    }
+    Ok(())
+}
+
+// --- History ---
+
+/// Number of captures kept in the history index.
+const HISTORY_LIMIT: usize = 200;
+
+/// A saved capture, as recorded in `$XDG_STATE_HOME/lunactl/screenshots.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct HistoryEntry {
+    path: PathBuf,
+    time: DateTime<Local>,
+    target: String,
+    geometry: Option<Geometry>,
+}
+
+fn history_path() -> Result<PathBuf> {
+    Ok(state::state_dir()?.join("screenshots.json"))
+}
+
+fn record_capture(file_path: &Path, target: &ScreenshotTarget, geometry: Option<Geometry>) -> Result<()> {
+    let path = history_path()?;
+    let mut entries: Vec<HistoryEntry> = state::load_json(&path)?;
+    push_history(&mut entries, HistoryEntry {
+        path: fs::canonicalize(file_path).unwrap_or_else(|_| file_path.to_path_buf()),
+        time: Local::now(),
+        target: target.kind().to_string(),
+        geometry,
+    });
+    state::save_json(&path, &entries)
+}
+
+/// Appends an entry, replacing an older one for the same file (an explicit
+/// path saved over), and drops the oldest beyond `HISTORY_LIMIT`.
+fn push_history(entries: &mut Vec<HistoryEntry>, entry: HistoryEntry) {
+    entries.retain(|e| e.path != entry.path);
+    entries.push(entry);
+    let excess = entries.len().saturating_sub(HISTORY_LIMIT);
+    entries.drain(..excess);
+}
+
+/// Recorded captures whose files still exist, most recent first.
+fn recent_captures() -> Result<Vec<HistoryEntry>> {
+    let entries: Vec<HistoryEntry> = state::load_json(&history_path()?)?;
+    Ok(entries.into_iter().rev().filter(|e| e.path.exists()).collect())
+}
+
+fn nth_capture(index: usize) -> Result<HistoryEntry> {
+    if index == 0 {
+        bail!("Screenshot indices start at 1");
+    }
+    recent_captures()?
+        .into_iter()
+        .nth(index - 1)
+        .ok_or_else(|| anyhow!("No screenshot #{} in history", index))
+}
+
+fn handle_history_action(action: &ScreenshotAction) -> Result<()> {
+    match action {
+        ScreenshotAction::List { limit, json } => {
+            let entries: Vec<HistoryEntry> = recent_captures()?.into_iter().take(*limit).collect();
+            if *json {
+                println!("{}", serde_json::to_string(&entries)?);
+                return Ok(());
+            }
+            for (i, entry) in entries.iter().enumerate() {
+                println!(
+                    "{:>3}  {}  {:<6}  {}",
+                    i + 1,
+                    entry.time.format("%Y-%m-%d %H:%M:%S"),
+                    entry.target,
+                    entry.path.display()
+                );
+            }
+        }
+        ScreenshotAction::Last => {
+            let entry = nth_capture(1)?;
+            let bytes = fs::read(&entry.path)
+                .with_context(|| format!("Failed to read {}", entry.path.display()))?;
+            let mime_type = image_format_for(&entry.path).to_mime_type();
+            copy_to_clipboard(&bytes, mime_type)?;
+            println!("Copied {}.", entry.path.display());
+        }
+        ScreenshotAction::Open { index } => {
+            let entry = nth_capture(*index)?;
+            Command::new("xdg-open")
+                .arg(&entry.path)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .context("Failed to execute xdg-open")?;
+        }
+        ScreenshotAction::Delete { index } => {
+            let entry = nth_capture(*index)?;
+            fs::remove_file(&entry.path)
+                .with_context(|| format!("Failed to delete {}", entry.path.display()))?;
+            let path = history_path()?;
+            let mut entries: Vec<HistoryEntry> = state::load_json(&path)?;
+            entries.retain(|e| e.path != entry.path);
+            state::save_json(&path, &entries)?;
+            println!("Deleted {}.", entry.path.display());
+        }
+        _ => {}
+    }
+    Ok(())
 }
 
 // --- Main Command Handler ---
@@ -175,73 +668,177 @@ pub fn handle_screenshot_command(args: &ScreenshotArgs, debug: bool) -> Result<(
         if debug {
             println!("Checking if required tools are installed...");
         }
-        let _ = check_command("grim", debug);
         let _ = check_command("slurp", debug);
-        let _ = check_command("hyprctl", debug);
         let _ = check_command("wl-copy", debug);
-        let _ = check_command("tee", debug);
+        let _ = check_command("hyprpicker", debug);
+        let _ = check_command("tesseract", debug);
+        // Only needed when wlr-screencopy is unavailable.
+        let _ = check_command("grim", debug);
         return Ok(());
     }
 
-    let mut grim_command = Command::new("grim");
-    if args.cursor {
-        grim_command.arg("-c");
+    if matches!(
+        args.action,
+        ScreenshotAction::List { .. } | ScreenshotAction::Last | ScreenshotAction::Open { .. } | ScreenshotAction::Delete { .. }
+    ) {
+        return handle_history_action(&args.action);
     }
 
-    let (action, target, path) = match &args.action {
-        ScreenshotAction::Save { target, path } => ("save", target, path),
-        ScreenshotAction::Copy { target } => ("copy", target, &None),
-        ScreenshotAction::Copysave { target, path } => ("copysave", target, path),
-        _ => return Ok(()), // Check is handled above
+    let (action, target, path, editor) = match &args.action {
+        ScreenshotAction::Save { target, path } => ("save", target, path, &None),
+        ScreenshotAction::Copy { target } => ("copy", target, &None, &None),
+        ScreenshotAction::Copysave { target, path } => ("copysave", target, path, &None),
+        ScreenshotAction::Edit { target, editor } => ("edit", target, &None, editor),
+        ScreenshotAction::Ocr { target, .. } => ("ocr", target, &None, &None),
+        ScreenshotAction::Qr { target } => ("qr", target, &None, &None),
+        _ => return Ok(()), // Check and history actions are handled above
     };
 
-    match target {
-        ScreenshotTarget::Active => grim_command.arg("-g").arg(get_active_window_geom()?), 
-        ScreenshotTarget::Area => grim_command.arg("-g").arg(get_area_geom()?), 
-        ScreenshotTarget::Output => grim_command.arg("-o").arg(get_active_output_name()?), 
-        ScreenshotTarget::Screen => &mut grim_command, // No extra args needed
-    };
+    if let Some(seconds) = args.wait {
+        thread::sleep(Duration::from_secs(seconds));
+    }
+
+    let ipc = HyprlandIpc::from_env()?;
+    let monitors = ipc.monitors()?;
+
+    let freeze = if args.freeze { Some(ScreenFreeze::start()?) } else { None };
+    let source = resolve_source(target, &ipc)?;
+    let capture = capture(&source, &monitors, args, debug)?;
+    drop(freeze);
+    let geometry = source.geometry();
+
+    // Text extraction works on the raw capture, without post-processing.
+    match &args.action {
+        ScreenshotAction::Ocr { lang, .. } => {
+            let text = recognize_text(&capture.image, lang)?;
+            if text.is_empty() {
+                bail!("No text recognized");
+            }
+            return copy_text_result("Text copied", &text, args.notify);
+        }
+        ScreenshotAction::Qr { .. } => {
+            let codes = decode_qr_codes(&capture.image);
+            if codes.is_empty() {
+                bail!("No QR code found");
+            }
+            return copy_text_result("QR code copied", &codes.join("\n"), args.notify);
+        }
+        _ => {}
+    }
+
+    let image = &post_process(capture, args)?;
 
     let file_path = match path {
         Some(p) => PathBuf::from(p),
-        None => get_target_directory()?.join(generate_filename()),
+        None => generate_file_path(&get_target_directory()?),
     };
 
-    match action {
+    let summary = match action {
         "save" => {
-            grim_command.arg(file_path);
-            let status = grim_command.status()?;
-            if status.success() {
-                println!("Screenshot saved.");
-            }
+            save_to_file(image, &file_path)?;
+            record_capture(&file_path, target, geometry)?;
+            println!("Screenshot saved.");
+            Some(("Screenshot saved", Some(file_path.as_path())))
         }
         "copy" => {
-            grim_command.arg("-"); // Output to stdout
-            let mut grim_process = grim_command.stdout(Stdio::piped()).spawn()?;
-            if let Some(stdout) = grim_process.stdout.take() {
-                let mut wl_copy_process = Command::new("wl-copy").stdin(stdout).spawn()?;
-                wl_copy_process.wait()?;
-            }
-            grim_process.wait()?;
+            copy_to_clipboard(&encode(image, ImageFormat::Png)?, "image/png")?;
             println!("Screenshot copied.");
+            Some(("Screenshot copied", None))
         }
         "copysave" => {
-            fs::create_dir_all(file_path.parent().unwrap())?;
-            grim_command.arg("-");
-            let mut grim_process = grim_command.stdout(Stdio::piped()).spawn()?;
-            if let Some(stdout) = grim_process.stdout.take() {
-                let mut tee_process = Command::new("tee").arg(&file_path).stdin(stdout).stdout(Stdio::piped()).spawn()?;
-                if let Some(tee_stdout) = tee_process.stdout.take() {
-                    let mut wl_copy_process = Command::new("wl-copy").stdin(tee_stdout).spawn()?;
-                    wl_copy_process.wait()?;
+            save_to_file(image, &file_path)?;
+            record_capture(&file_path, target, geometry)?;
+            copy_to_clipboard(&encode(image, ImageFormat::Png)?, "image/png")?;
+            println!("Screenshot saved and copied.");
+            Some(("Screenshot saved and copied", Some(file_path.as_path())))
+        }
+        "edit" => {
+            let editor = resolve_editor(editor)?;
+            let mut editor_command = Command::new(&editor[0]);
+            editor_command.args(&editor[1..]);
+
+            match editor_stdin_args(&editor[0]) {
+                Some(stdin_args) => {
+                    let mut editor_process = editor_command.args(stdin_args)
+                        .stdin(Stdio::piped())
+                        .spawn()
+                        .with_context(|| format!("Failed to launch editor: {}", editor[0]))?;
+                    if let Some(mut stdin) = editor_process.stdin.take() {
+                        stdin.write_all(&encode(image, ImageFormat::Png)?)?;
+                    }
+                }
+                None => {
+                    // Editors we don't know get the capture as a file argument.
+                    save_to_file(image, &file_path)?;
+                    record_capture(&file_path, target, geometry)?;
+                    editor_command.arg(&file_path).spawn()
+                        .with_context(|| format!("Failed to launch editor: {}", editor[0]))?;
                 }
-                tee_process.wait()?;
             }
-            grim_process.wait()?;
-            println!("Screenshot saved and copied.");
+            println!("Screenshot opened in {}.", editor[0]);
+            None
         }
-        _ => {},
+        _ => None,
+    };
+
+    if args.notify && let Some((summary, file_path)) = summary {
+        send_notification(summary, file_path)?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geometry_parses_slurp_output() {
+        assert_eq!(
+            Geometry::parse("10,-20 300x200\n").unwrap(),
+            Geometry { x: 10, y: -20, width: 300, height: 200 }
+        );
+        assert_eq!(Geometry::parse("0,0 1x1").unwrap().to_grim(), "0,0 1x1");
+    }
+
+    #[test]
+    fn geometry_rejects_malformed_input() {
+        for geom in ["", "10,20", "10,20 300", "10 20 300x200", "a,b cxd", "10,20 300x200x1"] {
+            let error = Geometry::parse(geom).unwrap_err().to_string();
+            assert_eq!(error, format!("Invalid geometry: {}", geom));
+        }
+    }
+
+    #[test]
+    fn unused_path_adds_a_suffix_on_collision() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = unused_path(dir.path(), "screenshot_1", "png");
+        assert_eq!(first, dir.path().join("screenshot_1.png"));
+        fs::write(&first, b"").unwrap();
+        let second = unused_path(dir.path(), "screenshot_1", "png");
+        assert_eq!(second, dir.path().join("screenshot_1-2.png"));
+        fs::write(&second, b"").unwrap();
+        assert_eq!(unused_path(dir.path(), "screenshot_1", "png"), dir.path().join("screenshot_1-3.png"));
+    }
+
+    fn entry(path: &str) -> HistoryEntry {
+        HistoryEntry { path: PathBuf::from(path), time: Local::now(), target: "area".into(), geometry: None }
+    }
+
+    #[test]
+    fn history_keeps_the_newest_entries() {
+        let mut entries: Vec<HistoryEntry> = (0..HISTORY_LIMIT).map(|i| entry(&format!("/s/{}.png", i))).collect();
+        push_history(&mut entries, entry("/s/new.png"));
+        assert_eq!(entries.len(), HISTORY_LIMIT);
+        assert_eq!(entries[0].path, PathBuf::from("/s/1.png"));
+        assert_eq!(entries.last().unwrap().path, PathBuf::from("/s/new.png"));
+    }
+
+    #[test]
+    fn history_replaces_entries_for_the_same_file() {
+        let mut entries = vec![entry("/s/a.png"), entry("/s/b.png")];
+        push_history(&mut entries, entry("/s/a.png"));
+        let paths: Vec<_> = entries.iter().map(|e| e.path.to_str().unwrap()).collect();
+        assert_eq!(paths, ["/s/b.png", "/s/a.png"]);
+    }
+}
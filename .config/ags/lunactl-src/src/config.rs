@@ -0,0 +1,311 @@
+use anyhow::{Context, Result};
+use json_comments::StripComments;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+use crate::state;
+
+/// Path of the AGS user configuration shared with luna-settings.
+pub fn config_path() -> PathBuf {
+    PathBuf::from(shellexpand::tilde("~/.ags/config.jsonc").into_owned())
+}
+
+/// `$XDG_CONFIG_HOME/hypr`, where the Hyprland and hypridle configs live.
+pub fn hypr_config_dir() -> PathBuf {
+    match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir).join("hypr"),
+        _ => PathBuf::from(shellexpand::tilde("~/.config/hypr").into_owned()),
+    }
+}
+
+/// Loads config.jsonc, or an empty object if the file does not exist.
+pub fn load() -> Result<Value> {
+    let path = config_path();
+    if !path.exists() {
+        return Ok(Value::Object(Default::default()));
+    }
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_reader(StripComments::new(content.as_bytes()))
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Deserializes a top-level section (e.g. `workspaces`) of config.jsonc.
+/// A missing file or section yields `T::default()`.
+pub fn section<T: DeserializeOwned + Default>(key: &str) -> Result<T> {
+    match load()?.get(key) {
+        Some(value) => serde_json::from_value(value.clone())
+            .with_context(|| format!("Invalid '{}' section in config.jsonc", key)),
+        None => Ok(T::default()),
+    }
+}
+
+/// A section as last read, tagged with the config.jsonc modification time.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct CachedSection {
+    modified: u128,
+    value: Value,
+}
+
+/// Like `section`, but served from `$XDG_RUNTIME_DIR/lunactl` while
+/// config.jsonc is unchanged, for commands that run on every keypress.
+pub fn cached_section<T: Serialize + DeserializeOwned + Default>(key: &str) -> Result<T> {
+    let modified = fs::metadata(config_path())
+        .and_then(|m| m.modified())
+        .map(|t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos())
+        .unwrap_or_default();
+    let cache_path = state::runtime_dir()?.join(format!("config-{}.json", key));
+    if modified > 0
+        && let Ok(cached) = state::load_json::<CachedSection>(&cache_path)
+        && cached.modified == modified
+        && let Ok(value) = serde_json::from_value(cached.value)
+    {
+        return Ok(value);
+    }
+
+    let value: T = section(key)?;
+    // The cache only saves time; failing to write it is not an error.
+    let _ = state::save_json(&cache_path, &CachedSection { modified, value: serde_json::to_value(&value)? });
+    Ok(value)
+}
+
+/// Sets numeric members of a top-level section of config.jsonc, adding the
+/// section (and the file) if needed. The file is edited line by line so
+/// comments and hand formatting survive.
+pub fn set_numbers(key: &str, values: &[(&str, u32)]) -> Result<()> {
+    let path = config_path();
+    let content = if path.exists() {
+        fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+    } else {
+        String::new()
+    };
+    let output = set_numbers_in(&content, key, values)
+        .with_context(|| format!("Failed to update {}", path.display()))?;
+    if output != content {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&path, output)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn set_numbers_in(content: &str, key: &str, values: &[(&str, u32)]) -> Result<String> {
+    if content.trim().is_empty() {
+        return set_numbers_in("{\n}\n", key, values);
+    }
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let section_prefix = format!("\"{}\":", key);
+
+    let mut depth = 0;
+    let mut start = None;
+    let mut end = None;
+    let mut found = vec![false; values.len()];
+    for (i, line) in lines.iter_mut().enumerate() {
+        let trimmed = line.trim_start();
+        if start.is_none() && depth == 1 && trimmed.starts_with(&section_prefix) && strip_comment(trimmed).trim_end().ends_with('{') {
+            start = Some(i);
+        } else if start.is_some() && depth == 2 {
+            let member = values.iter().position(|(name, _)| trimmed.starts_with(&format!("\"{}\":", name)));
+            if let Some(j) = member {
+                let (name, value) = values[j];
+                *line = replace_value(line, name, value);
+                found[j] = true;
+            }
+        }
+        depth += brace_delta(line);
+        if start.is_some() && depth == 1 {
+            end = Some(i);
+            break;
+        }
+    }
+    let (Some(start), Some(end)) = (start, end) else {
+        let with_section = insert_section(content, key)?;
+        return set_numbers_in(&with_section, key, values);
+    };
+
+    let has_members = lines[start + 1..end].iter().any(|l| {
+        let code = strip_comment(l.trim());
+        !code.is_empty()
+    });
+    let indent = lines[start + 1..end].iter()
+        .find(|l| !l.trim().is_empty())
+        .map(|l| l[..l.len() - l.trim_start().len()].to_string())
+        .unwrap_or_else(|| format!("{}  ", &lines[start][..lines[start].len() - lines[start].trim_start().len()]));
+    let missing: Vec<_> = values.iter().zip(&found).filter(|(_, f)| !**f).map(|(v, _)| v).collect();
+    for (n, (name, value)) in missing.iter().enumerate().rev() {
+        let comma = if has_members || n + 1 < missing.len() { "," } else { "" };
+        lines.insert(start + 1, format!("{}\"{}\": {}{}", indent, name, value, comma));
+    }
+
+    let mut output = lines.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+
+    // Refuse to write anything that does not read back as intended
+    let parsed: Value = serde_json::from_reader(StripComments::new(output.as_bytes()))
+        .context("Edited config.jsonc is no longer valid JSON")?;
+    for (name, value) in values {
+        if parsed.get(key).and_then(|s| s.get(name)).and_then(Value::as_u64) != Some(u64::from(*value)) {
+            anyhow::bail!("Could not set '{}.{}'", key, name);
+        }
+    }
+    Ok(output)
+}
+
+/// Adds an empty `"key": {}` section at the top of the root object.
+fn insert_section(content: &str, key: &str) -> Result<String> {
+    let root: Value = serde_json::from_reader(StripComments::new(content.as_bytes()))
+        .context("config.jsonc is not valid JSON")?;
+    let Some(root) = root.as_object() else {
+        anyhow::bail!("config.jsonc is not a JSON object");
+    };
+    if root.contains_key(key) {
+        anyhow::bail!("The '{}' section is not an object on its own lines", key);
+    }
+
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let Some(open) = lines.iter().position(|l| strip_comment(l.trim()).trim_end() == "{") else {
+        anyhow::bail!("The root object of config.jsonc does not start on its own line");
+    };
+    let indent = lines[open + 1..].iter()
+        .find(|l| !l.trim().is_empty())
+        .filter(|l| strip_comment(l.trim()) != "}")
+        .map(|l| l[..l.len() - l.trim_start().len()].to_string())
+        .unwrap_or_else(|| "  ".to_string());
+    let comma = if root.is_empty() { "" } else { "," };
+    lines.insert(open + 1, format!("{}\"{}\": {{", indent, key));
+    lines.insert(open + 2, format!("{}}}{}", indent, comma));
+
+    let mut output = lines.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// The part of a line before a `//` comment that is not inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    let bytes = line.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+        } else if b == b'"' {
+            in_string = true;
+        } else if b == b'/' && bytes.get(i + 1) == Some(&b'/') {
+            return &line[..i];
+        }
+    }
+    line
+}
+
+/// Change in object/array nesting depth over a line, ignoring strings and comments.
+fn brace_delta(line: &str) -> i32 {
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut delta = 0;
+    for b in strip_comment(line).bytes() {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'{' | b'[' => delta += 1,
+            b'}' | b']' => delta -= 1,
+            _ => {}
+        }
+    }
+    delta
+}
+
+/// Replaces the value in a `"name": value,` line, keeping any trailing comma or comment.
+fn replace_value(line: &str, name: &str, value: u32) -> String {
+    let prefix = format!("\"{}\":", name);
+    let at = line.find(&prefix).unwrap_or_default() + prefix.len();
+    let rest = &line[at..];
+    let value_start = rest.len() - rest.trim_start().len();
+    let value_len = rest[value_start..]
+        .find(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .unwrap_or(rest.len() - value_start);
+    format!("{}{}{}{}", &line[..at], &rest[..value_start], value, &rest[value_start + value_len..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+  // Comments survive
+  "bar": { "position": "top" },
+  "idle": {
+    "dim": 150,
+    "lock": 300, // five minutes
+    "inhibitClasses": ["mpv", "steam_app_*"],
+    "nested": { "dim": 1 }
+  },
+  "dock": {
+    "dim": 7
+  }
+}
+"#;
+
+    #[test]
+    fn set_numbers_rewrites_only_the_section() {
+        let output = set_numbers_in(CONFIG, "idle", &[("dim", 90), ("lock", 600)]).unwrap();
+        assert!(output.contains("    \"dim\": 90,\n"));
+        assert!(output.contains("    \"lock\": 600, // five minutes\n"));
+        assert!(output.contains("\"nested\": { \"dim\": 1 }"));
+        assert!(output.contains("    \"dim\": 7\n"));
+        assert!(output.contains("// Comments survive"));
+    }
+
+    #[test]
+    fn set_numbers_inserts_missing_keys() {
+        let output = set_numbers_in(CONFIG, "idle", &[("screenOff", 330), ("suspend", 1800)]).unwrap();
+        assert!(output.contains("  \"idle\": {\n    \"screenOff\": 330,\n    \"suspend\": 1800,\n    \"dim\": 150,"));
+
+        let empty = "{\n  \"idle\": {\n  }\n}\n";
+        let output = set_numbers_in(empty, "idle", &[("dim", 60), ("lock", 120)]).unwrap();
+        assert_eq!(output, "{\n  \"idle\": {\n    \"dim\": 60,\n    \"lock\": 120\n  }\n}\n");
+    }
+
+    #[test]
+    fn set_numbers_adds_a_missing_section() {
+        let output = set_numbers_in(CONFIG, "timeouts", &[("dim", 1)]).unwrap();
+        assert!(output.starts_with("{\n  \"timeouts\": {\n    \"dim\": 1\n  },\n  // Comments survive\n"));
+        assert!(output.contains("    \"dim\": 150,\n"));
+
+        let output = set_numbers_in("{\n}\n", "idle", &[("dim", 60)]).unwrap();
+        assert_eq!(output, "{\n  \"idle\": {\n    \"dim\": 60\n  }\n}\n");
+        assert_eq!(set_numbers_in("", "idle", &[("dim", 60)]).unwrap(), output);
+    }
+
+    #[test]
+    fn set_numbers_refuses_unsupported_layouts() {
+        assert!(set_numbers_in("{ \"idle\": { \"dim\": 1 } }", "idle", &[("dim", 2)]).is_err());
+        assert!(set_numbers_in("[]\n", "idle", &[("dim", 2)]).is_err());
+    }
+}
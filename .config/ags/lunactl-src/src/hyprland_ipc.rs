@@ -0,0 +1,710 @@
+use anyhow::{Context, Result, anyhow, bail};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::env;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const IO_TIMEOUT: Duration = Duration::from_secs(2);
+
+// --- Requests ---
+
+/// A request understood by Hyprland's `.socket.sock` control socket.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    Monitors,
+    Workspaces,
+    ActiveWorkspace,
+    Clients,
+    ActiveWindow,
+    Dispatch { dispatcher: String, args: String },
+    /// Sets a config value at runtime, e.g. `monitor DP-1,preferred,auto,1.5`.
+    Keyword { keyword: String, args: String },
+}
+
+impl Request {
+    /// Renders the request in the wire format expected by the socket.
+    /// Queries are prefixed with `j/` so that Hyprland answers with JSON.
+    pub fn to_command(&self) -> String {
+        match self {
+            Request::Monitors => "j/monitors".to_string(),
+            Request::Workspaces => "j/workspaces".to_string(),
+            Request::ActiveWorkspace => "j/activeworkspace".to_string(),
+            Request::Clients => "j/clients".to_string(),
+            Request::ActiveWindow => "j/activewindow".to_string(),
+            Request::Dispatch { dispatcher, args } if args.is_empty() => {
+                format!("dispatch {}", dispatcher)
+            }
+            Request::Dispatch { dispatcher, args } => format!("dispatch {} {}", dispatcher, args),
+            Request::Keyword { keyword, args } => format!("keyword {} {}", keyword, args),
+        }
+    }
+}
+
+// --- Responses ---
+
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct WorkspaceRef {
+    pub id: i32,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Monitor {
+    pub id: i32,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub width: i32,
+    pub height: i32,
+    #[serde(default)]
+    pub refresh_rate: f64,
+    pub x: i32,
+    pub y: i32,
+    pub active_workspace: WorkspaceRef,
+    #[serde(default)]
+    pub special_workspace: WorkspaceRef,
+    pub scale: f64,
+    #[serde(default)]
+    pub transform: i32,
+    pub focused: bool,
+    #[serde(default)]
+    pub disabled: bool,
+    /// Whether adaptive sync is active right now.
+    #[serde(default)]
+    pub vrr: bool,
+    /// DRM format, e.g. `XRGB8888`, or `XRGB2101010` for 10 bit.
+    #[serde(default)]
+    pub current_format: String,
+    /// ID of the monitor this one mirrors, or `none`.
+    #[serde(default)]
+    pub mirror_of: String,
+    /// e.g. `srgb` or `hdr`; missing before Hyprland 0.47.
+    #[serde(default)]
+    pub color_management_preset: Option<String>,
+    #[serde(default)]
+    pub sdr_brightness: Option<f64>,
+    #[serde(default)]
+    pub sdr_saturation: Option<f64>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Workspace {
+    pub id: i32,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub monitor: String,
+    #[serde(default, rename = "monitorID")]
+    pub monitor_id: i32,
+    #[serde(default)]
+    pub windows: u32,
+    #[serde(default, rename = "hasfullscreen")]
+    pub has_fullscreen: bool,
+    #[serde(default, rename = "lastwindow")]
+    pub last_window: String,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Client {
+    pub address: String,
+    #[serde(default)]
+    pub mapped: bool,
+    #[serde(default)]
+    pub hidden: bool,
+    pub at: [i32; 2],
+    pub size: [i32; 2],
+    pub workspace: WorkspaceRef,
+    #[serde(default)]
+    pub floating: bool,
+    pub monitor: i32,
+    #[serde(default)]
+    pub class: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub initial_class: String,
+    #[serde(default)]
+    pub pid: i32,
+    /// Older Hyprland releases report a bool, newer ones a fullscreen mode.
+    #[serde(default, deserialize_with = "bool_or_int")]
+    pub fullscreen: bool,
+    #[serde(default, rename = "focusHistoryID")]
+    pub focus_history_id: i32,
+}
+
+fn bool_or_int<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BoolOrInt {
+        Bool(bool),
+        Int(i64),
+    }
+    Ok(match BoolOrInt::deserialize(deserializer)? {
+        BoolOrInt::Bool(b) => b,
+        BoolOrInt::Int(i) => i != 0,
+    })
+}
+
+// --- Client ---
+
+/// Returns the directory holding the sockets of the running Hyprland instance.
+pub fn instance_dir() -> Result<PathBuf> {
+    let signature = env::var("HYPRLAND_INSTANCE_SIGNATURE")
+        .context("HYPRLAND_INSTANCE_SIGNATURE is not set, is Hyprland running?")?;
+
+    if let Ok(runtime_dir) = env::var("XDG_RUNTIME_DIR") {
+        let dir = PathBuf::from(runtime_dir).join("hypr").join(&signature);
+        if dir.exists() {
+            return Ok(dir);
+        }
+    }
+
+    // Hyprland versions before 0.40 kept their sockets under /tmp.
+    let legacy = PathBuf::from("/tmp/hypr").join(&signature);
+    if legacy.exists() {
+        return Ok(legacy);
+    }
+
+    Err(anyhow!("No socket directory found for Hyprland instance {}", signature))
+}
+
+/// A synchronous client for Hyprland's request socket.
+#[derive(Debug, Clone)]
+pub struct HyprlandIpc {
+    socket_path: PathBuf,
+}
+
+impl HyprlandIpc {
+    /// Connects to the Hyprland instance of the current session.
+    pub fn from_env() -> Result<Self> {
+        Ok(Self::with_socket(instance_dir()?.join(".socket.sock")))
+    }
+
+    /// Uses an explicit socket path, e.g. a fake server.
+    pub fn with_socket(socket_path: impl Into<PathBuf>) -> Self {
+        HyprlandIpc { socket_path: socket_path.into() }
+    }
+
+    /// Sends a request and returns the raw reply. Hyprland closes the
+    /// connection after answering, so the reply is read until EOF.
+    pub fn send(&self, request: &Request) -> Result<String> {
+        let mut stream = UnixStream::connect(&self.socket_path)
+            .with_context(|| format!("Failed to connect to {}", self.socket_path.display()))?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+        stream
+            .write_all(request.to_command().as_bytes())
+            .context("Failed to write to Hyprland socket")?;
+
+        let mut reply = String::new();
+        stream
+            .read_to_string(&mut reply)
+            .context("Failed to read from Hyprland socket")?;
+        Ok(reply)
+    }
+
+    fn query<T: DeserializeOwned>(&self, request: &Request) -> Result<T> {
+        let reply = self.send(request)?;
+        serde_json::from_str(&reply)
+            .with_context(|| format!("Failed to parse Hyprland reply to '{}'", request.to_command()))
+    }
+
+    pub fn monitors(&self) -> Result<Vec<Monitor>> {
+        self.query(&Request::Monitors)
+    }
+
+    pub fn focused_monitor(&self) -> Result<Monitor> {
+        self.monitors()?
+            .into_iter()
+            .find(|m| m.focused)
+            .ok_or_else(|| anyhow!("No focused monitor found"))
+    }
+
+    pub fn workspaces(&self) -> Result<Vec<Workspace>> {
+        self.query(&Request::Workspaces)
+    }
+
+    pub fn active_workspace(&self) -> Result<Workspace> {
+        self.query(&Request::ActiveWorkspace)
+    }
+
+    pub fn clients(&self) -> Result<Vec<Client>> {
+        self.query(&Request::Clients)
+    }
+
+    /// Returns the focused window, or `None` when no window has focus
+    /// (Hyprland answers with an empty object in that case).
+    pub fn active_window(&self) -> Result<Option<Client>> {
+        let value: serde_json::Value = self.query(&Request::ActiveWindow)?;
+        if value.as_object().is_some_and(|o| o.is_empty()) {
+            return Ok(None);
+        }
+        serde_json::from_value(value)
+            .map(Some)
+            .context("Failed to parse active window")
+    }
+
+    pub fn dispatch(&self, dispatcher: &str, args: &str) -> Result<()> {
+        let reply = self.send(&Request::Dispatch {
+            dispatcher: dispatcher.to_string(),
+            args: args.to_string(),
+        })?;
+        if reply.trim() != "ok" {
+            bail!("Hyprland dispatch '{} {}' failed: {}", dispatcher, args, reply.trim());
+        }
+        Ok(())
+    }
+
+    pub fn keyword(&self, keyword: &str, args: &str) -> Result<()> {
+        let reply = self.send(&Request::Keyword {
+            keyword: keyword.to_string(),
+            args: args.to_string(),
+        })?;
+        if reply.trim() != "ok" {
+            bail!("Hyprland keyword '{} {}' failed: {}", keyword, args, reply.trim());
+        }
+        Ok(())
+    }
+}
+
+// --- Events ---
+
+/// An event read from Hyprland's `.socket2.sock` event socket.
+///
+/// Serialized with an `event` tag carrying Hyprland's own event name, so
+/// `workspace>>3` becomes `{"event":"workspace","name":"3"}`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum Event {
+    Workspace { name: String },
+    WorkspaceV2 { id: i32, name: String },
+    FocusedMon { monitor: String, workspace: String },
+    ActiveWindow { class: String, title: String },
+    ActiveWindowV2 { address: String },
+    Fullscreen { enabled: bool },
+    MonitorAdded { name: String },
+    MonitorAddedV2 { id: i32, name: String, description: String },
+    MonitorRemoved { name: String },
+    CreateWorkspace { name: String },
+    CreateWorkspaceV2 { id: i32, name: String },
+    DestroyWorkspace { name: String },
+    DestroyWorkspaceV2 { id: i32, name: String },
+    MoveWorkspace { workspace: String, monitor: String },
+    RenameWorkspace { id: i32, name: String },
+    ActiveSpecial { workspace: String, monitor: String },
+    ActiveLayout { keyboard: String, layout: String },
+    OpenWindow { address: String, workspace: String, class: String, title: String },
+    CloseWindow { address: String },
+    MoveWindow { address: String, workspace: String },
+    WindowTitle { address: String },
+    Urgent { address: String },
+    Submap { name: String },
+    ChangeFloatingMode { address: String, floating: bool },
+    /// Any event lunactl does not model, passed through verbatim, e.g.
+    /// `{"event":"configreloaded","data":""}`.
+    #[serde(untagged)]
+    Other {
+        #[serde(rename = "event")]
+        name: String,
+        data: String,
+    },
+}
+
+impl Event {
+    /// Parses a raw `EVENT>>DATA` line. Returns `None` for lines without
+    /// the `>>` separator; events with unexpected data become `Other`.
+    pub fn parse(line: &str) -> Option<Event> {
+        let (name, data) = line.trim_end().split_once(">>")?;
+        Some(Self::parse_known(name, data).unwrap_or_else(|| Event::Other {
+            name: name.to_string(),
+            data: data.to_string(),
+        }))
+    }
+
+    fn parse_known(name: &str, data: &str) -> Option<Event> {
+        // Only the last field may contain commas (window titles, descriptions).
+        let fields = |n: usize| -> Option<Vec<String>> {
+            let parts: Vec<String> = data.splitn(n, ',').map(str::to_string).collect();
+            (parts.len() == n).then_some(parts)
+        };
+        let one = || data.to_string();
+        let id_name = || -> Option<(i32, String)> {
+            let (id, name) = data.split_once(',')?;
+            Some((id.parse().ok()?, name.to_string()))
+        };
+
+        let event = match name {
+            "workspace" => Event::Workspace { name: one() },
+            "workspacev2" => {
+                let (id, name) = id_name()?;
+                Event::WorkspaceV2 { id, name }
+            }
+            "focusedmon" => {
+                let [monitor, workspace] = <[String; 2]>::try_from(fields(2)?).ok()?;
+                Event::FocusedMon { monitor, workspace }
+            }
+            "activewindow" => {
+                let [class, title] = <[String; 2]>::try_from(fields(2)?).ok()?;
+                Event::ActiveWindow { class, title }
+            }
+            "activewindowv2" => Event::ActiveWindowV2 { address: one() },
+            "fullscreen" => Event::Fullscreen { enabled: data == "1" },
+            "monitoradded" => Event::MonitorAdded { name: one() },
+            "monitoraddedv2" => {
+                let [id, name, description] = <[String; 3]>::try_from(fields(3)?).ok()?;
+                Event::MonitorAddedV2 { id: id.parse().ok()?, name, description }
+            }
+            "monitorremoved" => Event::MonitorRemoved { name: one() },
+            "createworkspace" => Event::CreateWorkspace { name: one() },
+            "createworkspacev2" => {
+                let (id, name) = id_name()?;
+                Event::CreateWorkspaceV2 { id, name }
+            }
+            "destroyworkspace" => Event::DestroyWorkspace { name: one() },
+            "destroyworkspacev2" => {
+                let (id, name) = id_name()?;
+                Event::DestroyWorkspaceV2 { id, name }
+            }
+            "moveworkspace" => {
+                let [workspace, monitor] = <[String; 2]>::try_from(fields(2)?).ok()?;
+                Event::MoveWorkspace { workspace, monitor }
+            }
+            "renameworkspace" => {
+                let (id, name) = id_name()?;
+                Event::RenameWorkspace { id, name }
+            }
+            "activespecial" => {
+                let [workspace, monitor] = <[String; 2]>::try_from(fields(2)?).ok()?;
+                Event::ActiveSpecial { workspace, monitor }
+            }
+            "activelayout" => {
+                let [keyboard, layout] = <[String; 2]>::try_from(fields(2)?).ok()?;
+                Event::ActiveLayout { keyboard, layout }
+            }
+            "openwindow" => {
+                let [address, workspace, class, title] = <[String; 4]>::try_from(fields(4)?).ok()?;
+                Event::OpenWindow { address, workspace, class, title }
+            }
+            "closewindow" => Event::CloseWindow { address: one() },
+            "movewindow" => {
+                let [address, workspace] = <[String; 2]>::try_from(fields(2)?).ok()?;
+                Event::MoveWindow { address, workspace }
+            }
+            "windowtitle" => Event::WindowTitle { address: one() },
+            "urgent" => Event::Urgent { address: one() },
+            "submap" => Event::Submap { name: one() },
+            "changefloatingmode" => {
+                let [address, floating] = <[String; 2]>::try_from(fields(2)?).ok()?;
+                Event::ChangeFloatingMode { address, floating: floating == "1" }
+            }
+            _ => return None,
+        };
+        Some(event)
+    }
+
+    /// Hyprland's name for this event, as used by `--only` filters.
+    pub fn name(&self) -> &str {
+        match self {
+            Event::Workspace { .. } => "workspace",
+            Event::WorkspaceV2 { .. } => "workspacev2",
+            Event::FocusedMon { .. } => "focusedmon",
+            Event::ActiveWindow { .. } => "activewindow",
+            Event::ActiveWindowV2 { .. } => "activewindowv2",
+            Event::Fullscreen { .. } => "fullscreen",
+            Event::MonitorAdded { .. } => "monitoradded",
+            Event::MonitorAddedV2 { .. } => "monitoraddedv2",
+            Event::MonitorRemoved { .. } => "monitorremoved",
+            Event::CreateWorkspace { .. } => "createworkspace",
+            Event::CreateWorkspaceV2 { .. } => "createworkspacev2",
+            Event::DestroyWorkspace { .. } => "destroyworkspace",
+            Event::DestroyWorkspaceV2 { .. } => "destroyworkspacev2",
+            Event::MoveWorkspace { .. } => "moveworkspace",
+            Event::RenameWorkspace { .. } => "renameworkspace",
+            Event::ActiveSpecial { .. } => "activespecial",
+            Event::ActiveLayout { .. } => "activelayout",
+            Event::OpenWindow { .. } => "openwindow",
+            Event::CloseWindow { .. } => "closewindow",
+            Event::MoveWindow { .. } => "movewindow",
+            Event::WindowTitle { .. } => "windowtitle",
+            Event::Urgent { .. } => "urgent",
+            Event::Submap { .. } => "submap",
+            Event::ChangeFloatingMode { .. } => "changefloatingmode",
+            Event::Other { name, .. } => name,
+        }
+    }
+}
+
+/// A blocking iterator over events from Hyprland's event socket.
+pub struct EventStream {
+    reader: BufReader<UnixStream>,
+}
+
+impl EventStream {
+    /// Subscribes to the event socket of the current Hyprland instance.
+    pub fn connect() -> Result<Self> {
+        Self::with_socket(instance_dir()?.join(".socket2.sock"))
+    }
+
+    pub fn with_socket(socket_path: impl AsRef<Path>) -> Result<Self> {
+        let socket_path = socket_path.as_ref();
+        let stream = UnixStream::connect(socket_path)
+            .with_context(|| format!("Failed to connect to {}", socket_path.display()))?;
+        Ok(EventStream { reader: BufReader::new(stream) })
+    }
+}
+
+impl Iterator for EventStream {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) => {
+                    if let Some(event) = Event::parse(&line) {
+                        return Some(Ok(event));
+                    }
+                }
+                Err(e) => return Some(Err(anyhow!("Failed to read Hyprland event: {}", e))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::thread::{self, JoinHandle};
+    use tempfile::TempDir;
+
+    /// Serves one connection with `reply` and hands back the request bytes.
+    fn fake_server(reply: &'static str) -> (TempDir, HyprlandIpc, JoinHandle<String>) {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join(".socket.sock");
+        let listener = UnixListener::bind(&socket_path).unwrap();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0u8; 1024];
+            let read = stream.read(&mut buffer).unwrap();
+            stream.write_all(reply.as_bytes()).unwrap();
+            String::from_utf8(buffer[..read].to_vec()).unwrap()
+        });
+        (dir, HyprlandIpc::with_socket(socket_path), handle)
+    }
+
+    const MONITORS: &str = r#"[{
+        "id": 0, "name": "DP-1", "description": "Dell U2720Q", "width": 3840, "height": 2160,
+        "refreshRate": 59.997, "x": 0, "y": 0,
+        "activeWorkspace": {"id": 3, "name": "3"}, "specialWorkspace": {"id": 0, "name": ""},
+        "scale": 1.5, "transform": 0, "focused": true, "disabled": false,
+        "vrr": true, "currentFormat": "XRGB2101010", "mirrorOf": "none",
+        "colorManagementPreset": "hdr", "sdrBrightness": 1.2, "sdrSaturation": 1.0
+    }, {
+        "id": 1, "name": "HDMI-A-1", "width": 1920, "height": 1080,
+        "x": 2560, "y": 0, "activeWorkspace": {"id": 11, "name": "11"},
+        "scale": 1.0, "focused": false
+    }]"#;
+
+    #[test]
+    fn monitors_sends_json_query_and_parses_reply() {
+        let (_dir, ipc, server) = fake_server(MONITORS);
+        let monitors = ipc.monitors().unwrap();
+        assert_eq!(server.join().unwrap(), "j/monitors");
+
+        assert_eq!(monitors.len(), 2);
+        assert_eq!(monitors[0].name, "DP-1");
+        assert_eq!(monitors[0].scale, 1.5);
+        assert_eq!(monitors[0].active_workspace, WorkspaceRef { id: 3, name: "3".to_string() });
+        assert!(monitors[0].focused);
+        // Optional fields fall back to their defaults.
+        assert_eq!(monitors[1].description, "");
+        assert_eq!(monitors[1].refresh_rate, 0.0);
+        assert_eq!(monitors[1].special_workspace, WorkspaceRef::default());
+        assert!(monitors[0].vrr && !monitors[1].vrr);
+        assert_eq!(monitors[0].current_format, "XRGB2101010");
+        assert_eq!(monitors[0].color_management_preset.as_deref(), Some("hdr"));
+        assert_eq!(monitors[0].sdr_brightness, Some(1.2));
+        assert_eq!(monitors[1].mirror_of, "");
+        assert_eq!(monitors[1].color_management_preset, None);
+    }
+
+    #[test]
+    fn focused_monitor_picks_the_focused_one() {
+        let (_dir, ipc, _server) = fake_server(MONITORS);
+        assert_eq!(ipc.focused_monitor().unwrap().name, "DP-1");
+    }
+
+    #[test]
+    fn clients_parse_both_fullscreen_formats() {
+        let (_dir, ipc, server) = fake_server(r#"[{
+            "address": "0x5581a0b0", "mapped": true, "hidden": false, "at": [10, 42], "size": [800, 600],
+            "workspace": {"id": 2, "name": "2"}, "floating": false, "monitor": 0,
+            "class": "kitty", "title": "~", "initialClass": "kitty", "pid": 4242,
+            "fullscreen": 2, "focusHistoryID": 0
+        }, {
+            "address": "0x5581c0d0", "at": [0, 0], "size": [1920, 1080],
+            "workspace": {"id": 3}, "monitor": 1, "fullscreen": false
+        }]"#);
+        let clients = ipc.clients().unwrap();
+        assert_eq!(server.join().unwrap(), "j/clients");
+
+        assert_eq!(clients[0].address, "0x5581a0b0");
+        assert_eq!(clients[0].at, [10, 42]);
+        assert_eq!(clients[0].size, [800, 600]);
+        assert_eq!(clients[0].class, "kitty");
+        assert_eq!(clients[0].pid, 4242);
+        assert!(clients[0].fullscreen);
+        assert!(!clients[1].fullscreen);
+        assert_eq!(clients[1].workspace.name, "");
+    }
+
+    #[test]
+    fn active_window_is_none_for_empty_object() {
+        let (_dir, ipc, server) = fake_server("{}");
+        assert_eq!(ipc.active_window().unwrap(), None);
+        assert_eq!(server.join().unwrap(), "j/activewindow");
+    }
+
+    #[test]
+    fn dispatch_sends_dispatcher_and_args() {
+        let (_dir, ipc, server) = fake_server("ok");
+        ipc.dispatch("workspace", "12").unwrap();
+        assert_eq!(server.join().unwrap(), "dispatch workspace 12");
+    }
+
+    #[test]
+    fn dispatch_without_args_has_no_trailing_space() {
+        let (_dir, ipc, server) = fake_server("ok");
+        ipc.dispatch("focuscurrentorlast", "").unwrap();
+        assert_eq!(server.join().unwrap(), "dispatch focuscurrentorlast");
+    }
+
+    #[test]
+    fn dispatch_reports_hyprland_error() {
+        let (_dir, ipc, _server) = fake_server("Invalid dispatcher\n");
+        let error = ipc.dispatch("bogus", "1").unwrap_err().to_string();
+        assert_eq!(error, "Hyprland dispatch 'bogus 1' failed: Invalid dispatcher");
+    }
+
+    #[test]
+    fn malformed_reply_is_an_error() {
+        let (_dir, ipc, _server) = fake_server("unknown request");
+        let error = ipc.monitors().unwrap_err().to_string();
+        assert_eq!(error, "Failed to parse Hyprland reply to 'j/monitors'");
+    }
+
+    #[test]
+    fn parses_simple_events() {
+        assert_eq!(Event::parse("workspace>>3\n"), Some(Event::Workspace { name: "3".to_string() }));
+        assert_eq!(Event::parse("fullscreen>>1"), Some(Event::Fullscreen { enabled: true }));
+        assert_eq!(Event::parse("fullscreen>>0"), Some(Event::Fullscreen { enabled: false }));
+        assert_eq!(
+            Event::parse("workspacev2>>11,web"),
+            Some(Event::WorkspaceV2 { id: 11, name: "web".to_string() })
+        );
+        assert_eq!(
+            Event::parse("focusedmon>>DP-1,3"),
+            Some(Event::FocusedMon { monitor: "DP-1".to_string(), workspace: "3".to_string() })
+        );
+    }
+
+    #[test]
+    fn only_the_last_field_keeps_commas() {
+        assert_eq!(
+            Event::parse("activewindow>>firefox,Hello, world - Mozilla Firefox"),
+            Some(Event::ActiveWindow {
+                class: "firefox".to_string(),
+                title: "Hello, world - Mozilla Firefox".to_string(),
+            })
+        );
+        assert_eq!(
+            Event::parse("openwindow>>5581a0b0,2,kitty,vim a.rs, b.rs"),
+            Some(Event::OpenWindow {
+                address: "5581a0b0".to_string(),
+                workspace: "2".to_string(),
+                class: "kitty".to_string(),
+                title: "vim a.rs, b.rs".to_string(),
+            })
+        );
+        assert_eq!(
+            Event::parse("monitoraddedv2>>1,HDMI-A-1,LG, 27in"),
+            Some(Event::MonitorAddedV2 { id: 1, name: "HDMI-A-1".to_string(), description: "LG, 27in".to_string() })
+        );
+    }
+
+    #[test]
+    fn unknown_or_malformed_events_become_other() {
+        assert_eq!(
+            Event::parse("configreloaded>>"),
+            Some(Event::Other { name: "configreloaded".to_string(), data: String::new() })
+        );
+        // A non-numeric id does not fit workspacev2.
+        assert_eq!(
+            Event::parse("workspacev2>>abc,web"),
+            Some(Event::Other { name: "workspacev2".to_string(), data: "abc,web".to_string() })
+        );
+        assert_eq!(
+            Event::parse("openwindow>>5581a0b0,2"),
+            Some(Event::Other { name: "openwindow".to_string(), data: "5581a0b0,2".to_string() })
+        );
+    }
+
+    #[test]
+    fn lines_without_separator_are_skipped() {
+        assert_eq!(Event::parse(""), None);
+        assert_eq!(Event::parse("workspace 3"), None);
+    }
+
+    #[test]
+    fn event_name_round_trips() {
+        for line in ["submap>>resize", "changefloatingmode>>5581a0b0,1", "custom>>data"] {
+            let event = Event::parse(line).unwrap();
+            assert_eq!(event.name(), line.split_once(">>").unwrap().0);
+        }
+    }
+
+    #[test]
+    fn events_serialize_with_event_tag() {
+        let event = Event::parse("workspace>>3").unwrap();
+        assert_eq!(serde_json::to_string(&event).unwrap(), r#"{"event":"workspace","name":"3"}"#);
+        let event = Event::parse("configreloaded>>").unwrap();
+        assert_eq!(serde_json::to_string(&event).unwrap(), r#"{"event":"configreloaded","data":""}"#);
+    }
+
+    #[test]
+    fn event_stream_reads_events_until_eof() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join(".socket2.sock");
+        let listener = UnixListener::bind(&socket_path).unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"workspace>>2\nnot an event\nsubmap>>resize\n").unwrap();
+        });
+        let events: Vec<Event> = EventStream::with_socket(&socket_path)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        server.join().unwrap();
+        assert_eq!(
+            events,
+            vec![Event::Workspace { name: "2".to_string() }, Event::Submap { name: "resize".to_string() }]
+        );
+    }
+
+    #[test]
+    fn missing_socket_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join(".socket.sock");
+        let error = HyprlandIpc::with_socket(&socket_path).monitors().unwrap_err().to_string();
+        assert_eq!(error, format!("Failed to connect to {}", socket_path.display()));
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::{Result, anyhow};
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+
+/// Shadow opacity, blur radius and vertical offset, relative to one logical pixel.
+const SHADOW_OPACITY: f32 = 0.45;
+const SHADOW_BLUR: f32 = 12.0;
+const SHADOW_OFFSET: f32 = 6.0;
+
+/// Parses `#rgb`, `#rrggbb`, `#rrggbbaa` or `transparent`.
+pub fn parse_color(color: &str) -> Result<Rgba<u8>> {
+    if color.eq_ignore_ascii_case("transparent") {
+        return Ok(Rgba([0, 0, 0, 0]));
+    }
+    let invalid = || anyhow!("Invalid color: {} (expected #rrggbb or #rrggbbaa)", color);
+    let hex = color.strip_prefix('#').ok_or_else(invalid)?;
+    // Also keeps the byte slicing below on character boundaries.
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let hex = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect::<String>() + "ff",
+        6 => format!("{}ff", hex),
+        8 => hex.to_string(),
+        _ => return Err(invalid()),
+    };
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    Ok(Rgba([channel(0)?, channel(2)?, channel(4)?, channel(6)?]))
+}
+
+/// Resizes the image by `factor`.
+pub fn scale(image: &RgbaImage, factor: f64) -> RgbaImage {
+    let width = (image.width() as f64 * factor).round().max(1.0) as u32;
+    let height = (image.height() as f64 * factor).round().max(1.0) as u32;
+    imageops::resize(image, width, height, FilterType::Lanczos3)
+}
+
+/// Makes the corners transparent outside a circle of `radius`, with
+/// anti-aliased edges.
+pub fn round_corners(image: &mut RgbaImage, radius: f64) {
+    let (width, height) = image.dimensions();
+    let radius = radius.min(width as f64 / 2.0).min(height as f64 / 2.0);
+    if radius <= 0.0 {
+        return;
+    }
+    let span = radius.ceil() as u32;
+
+    for y in 0..span.min(height) {
+        for x in 0..span.min(width) {
+            // Distance from the pixel center to the corner circle's center.
+            let dx = radius - (x as f64 + 0.5);
+            let dy = radius - (y as f64 + 0.5);
+            if dx <= 0.0 || dy <= 0.0 {
+                continue;
+            }
+            let coverage = (radius - (dx * dx + dy * dy).sqrt() + 0.5).clamp(0.0, 1.0);
+            if coverage >= 1.0 {
+                continue;
+            }
+            for (px, py) in [
+                (x, y),
+                (width - 1 - x, y),
+                (x, height - 1 - y),
+                (width - 1 - x, height - 1 - y),
+            ] {
+                let pixel = image.get_pixel_mut(px, py);
+                pixel[3] = (pixel[3] as f64 * coverage).round() as u8;
+            }
+        }
+    }
+}
+
+/// Places the image on a larger transparent canvas above a blurred drop shadow.
+/// `ratio` is the number of image pixels per logical pixel.
+pub fn add_shadow(image: &RgbaImage, ratio: f64) -> RgbaImage {
+    let blur = SHADOW_BLUR * ratio as f32;
+    let offset = (SHADOW_OFFSET * ratio as f32).round() as u32;
+    let margin = (blur * 2.0).ceil() as u32 + offset;
+    let (width, height) = (image.width() + margin * 2, image.height() + margin * 2);
+
+    let mut shadow = RgbaImage::new(width, height);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let alpha = (pixel[3] as f32 * SHADOW_OPACITY) as u8;
+        shadow.put_pixel(x + margin, y + margin + offset, Rgba([0, 0, 0, alpha]));
+    }
+    let mut canvas = imageops::blur(&shadow, blur / 2.0);
+    imageops::overlay(&mut canvas, image, margin as i64, margin as i64);
+    canvas
+}
+
+/// Surrounds the image with `amount` pixels of `background`.
+pub fn pad(image: &RgbaImage, amount: u32, background: Rgba<u8>) -> RgbaImage {
+    let (width, height) = (image.width() + amount * 2, image.height() + amount * 2);
+    let mut canvas = RgbaImage::from_pixel(width, height, background);
+    imageops::overlay(&mut canvas, image, amount as i64, amount as i64);
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_color_accepts_the_documented_forms() {
+        assert_eq!(parse_color("#fff").unwrap(), Rgba([255, 255, 255, 255]));
+        assert_eq!(parse_color("#1e2030").unwrap(), Rgba([0x1e, 0x20, 0x30, 255]));
+        assert_eq!(parse_color("#1E203080").unwrap(), Rgba([0x1e, 0x20, 0x30, 0x80]));
+        assert_eq!(parse_color("Transparent").unwrap(), Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn parse_color_rejects_everything_else() {
+        for color in ["fff", "#ff", "#fffff", "#ggg", "#aéééa", "#ééé", "#12345é", ""] {
+            assert!(parse_color(color).is_err(), "{} should be rejected", color);
+        }
+    }
+
+    #[test]
+    fn scale_rounds_and_keeps_at_least_one_pixel() {
+        let image = RgbaImage::new(100, 50);
+        assert_eq!(scale(&image, 1.5).dimensions(), (150, 75));
+        assert_eq!(scale(&image, 0.001).dimensions(), (1, 1));
+    }
+
+    #[test]
+    fn round_corners_clears_only_the_corners() {
+        let mut image = RgbaImage::from_pixel(20, 10, Rgba([255, 0, 0, 255]));
+        round_corners(&mut image, 4.0);
+        for (x, y) in [(0, 0), (19, 0), (0, 9), (19, 9)] {
+            assert_eq!(image.get_pixel(x, y)[3], 0);
+        }
+        // Edges away from the corners and the middle are untouched.
+        assert_eq!(image.get_pixel(10, 0)[3], 255);
+        assert_eq!(image.get_pixel(0, 5)[3], 255);
+        assert_eq!(image.get_pixel(10, 5)[3], 255);
+        // The curve is anti-aliased.
+        let edge = image.get_pixel(1, 1)[3];
+        assert!(edge > 0 && edge < 255, "alpha {}", edge);
+    }
+
+    #[test]
+    fn round_corners_clamps_the_radius() {
+        let mut image = RgbaImage::from_pixel(6, 6, Rgba([0, 0, 0, 255]));
+        round_corners(&mut image, 100.0);
+        assert_eq!(image.get_pixel(0, 0)[3], 0);
+        assert_eq!(image.get_pixel(3, 3)[3], 255);
+
+        let mut image = RgbaImage::from_pixel(6, 6, Rgba([0, 0, 0, 255]));
+        round_corners(&mut image, 0.0);
+        assert!(image.pixels().all(|p| p[3] == 255));
+    }
+
+    #[test]
+    fn add_shadow_grows_with_the_pixel_ratio() {
+        let image = RgbaImage::from_pixel(10, 10, Rgba([255, 255, 255, 255]));
+        // margin = 2 * blur + offset, all in image pixels
+        assert_eq!(add_shadow(&image, 1.0).dimensions(), (10 + 2 * 30, 10 + 2 * 30));
+        assert_eq!(add_shadow(&image, 2.0).dimensions(), (10 + 2 * 60, 10 + 2 * 60));
+
+        let canvas = add_shadow(&image, 1.0);
+        assert_eq!(*canvas.get_pixel(35, 35), Rgba([255, 255, 255, 255]));
+        assert_eq!(canvas.get_pixel(0, 0)[3], 0);
+        // The shadow falls below the image.
+        assert!(canvas.get_pixel(35, 47)[3] > canvas.get_pixel(35, 22)[3]);
+    }
+
+    #[test]
+    fn pad_surrounds_with_the_background() {
+        let image = RgbaImage::from_pixel(4, 2, Rgba([255, 255, 255, 255]));
+        let padded = pad(&image, 3, Rgba([1, 2, 3, 255]));
+        assert_eq!(padded.dimensions(), (10, 8));
+        assert_eq!(*padded.get_pixel(0, 0), Rgba([1, 2, 3, 255]));
+        assert_eq!(*padded.get_pixel(3, 3), Rgba([255, 255, 255, 255]));
+    }
+}
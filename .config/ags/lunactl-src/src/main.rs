@@ -1,6 +1,13 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 mod commands;
+mod config;
+mod glob;
+mod hyprland_ipc;
+mod image_effects;
+mod notify;
+mod screencopy;
+mod state;
 use commands::network::NetworkArgs;
 
 use commands::screenshot::ScreenshotArgs;
@@ -33,6 +40,9 @@ enum Commands {
     Screenshot(ScreenshotArgs),
     /// Prevents the system from becoming idle
     IdleInhibitor(IdleInhibitorArgs),
+    /// hypridle configuration
+    #[command(subcommand)]
+    Idle(commands::idle::IdleCommands),
     Recorder(RecorderArgs),
     RestartAgs(commands::restart_ags::RestartAgsArgs),
     Scale(commands::scale::ScaleArgs),
@@ -49,6 +59,7 @@ fn main() -> Result<()> {
         Commands::Network(command) => commands::network::handle_network_command(command, cli.debug),
         Commands::Screenshot(args) => commands::screenshot::handle_screenshot_command(args, cli.debug),
         Commands::IdleInhibitor(args) => commands::idle_inhibitor::handle_idle_inhibitor_command(args, cli.debug),
+        Commands::Idle(command) => commands::idle::handle_idle_command(command, cli.debug),
         Commands::Recorder(args) => commands::recorder::handle_recorder_command(args, cli.debug),
         Commands::RestartAgs(args) => commands::restart_ags::handle_restart_ags_command(args, cli.debug),
         Commands::Scale(args) => commands::scale::handle_scale_command(args, cli.debug),
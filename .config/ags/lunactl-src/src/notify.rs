@@ -0,0 +1,75 @@
+use anyhow::{Context, Result, anyhow};
+use notify_rust::Notification;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Shows a notification for a saved file with Open, Open folder, Copy path
+/// and Delete actions, then blocks until one is chosen or the notification
+/// is dismissed and performs it.
+pub fn notify_file(summary: &str, body: &str, path: &Path, icon: Option<&Path>) -> Result<()> {
+    let mut notification = Notification::new();
+    notification
+        .summary(summary)
+        .body(body)
+        .appname("lunactl")
+        .action("open", "Open")
+        .action("folder", "Open folder")
+        .action("copy", "Copy path")
+        .action("delete", "Delete");
+    if let Some(icon) = icon {
+        notification.icon(&icon.to_string_lossy());
+    }
+
+    let handle = notification
+        .show()
+        .map_err(|e| anyhow!("Failed to show notification: {}", e))?;
+
+    let mut chosen = None;
+    handle.wait_for_action(|action| chosen = Some(action.to_string()));
+
+    match chosen.as_deref() {
+        Some("open") => open(path),
+        Some("folder") => open(path.parent().unwrap_or(Path::new("/"))),
+        Some("copy") => copy_path(path),
+        Some("delete") => fs::remove_file(path)
+            .with_context(|| format!("Failed to delete {}", path.display())),
+        // "__closed", or the server does not support actions.
+        _ => Ok(()),
+    }
+}
+
+/// Formats a byte count as e.g. `12.3 MiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+fn open(path: &Path) -> Result<()> {
+    Command::new("xdg-open")
+        .arg(path)
+        .spawn()
+        .context("Failed to execute xdg-open")?;
+    Ok(())
+}
+
+fn copy_path(path: &Path) -> Result<()> {
+    let status = Command::new("wl-copy")
+        .arg(path)
+        .status()
+        .context("Failed to execute wl-copy")?;
+    if !status.success() {
+        return Err(anyhow!("wl-copy failed to copy the path"));
+    }
+    Ok(())
+}
@@ -0,0 +1,294 @@
+use anyhow::{Context, Result, anyhow, bail};
+use image::RgbaImage;
+use memmap2::Mmap;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::os::fd::AsFd;
+use std::path::PathBuf;
+use std::process;
+
+use wayland_client::protocol::{wl_buffer, wl_output, wl_registry, wl_shm, wl_shm_pool};
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle, WEnum};
+
+use wayland_protocols_wlr::screencopy::v1::client::{
+    zwlr_screencopy_frame_v1,
+    zwlr_screencopy_manager_v1,
+};
+
+/// A rectangle in output-local logical coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ShmFormat {
+    format: wl_shm::Format,
+    width: u32,
+    height: u32,
+    stride: u32,
+}
+
+#[derive(Debug, Default, PartialEq)]
+enum FrameState {
+    #[default]
+    Pending,
+    Ready,
+    Failed,
+}
+
+// The state of a single capture.
+#[derive(Default)]
+struct AppState {
+    shm: Option<wl_shm::WlShm>,
+    manager: Option<zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1>,
+    outputs: Vec<(wl_output::WlOutput, Option<String>)>,
+    buffer_format: Option<ShmFormat>,
+    buffer_done: bool,
+    y_invert: bool,
+    frame_state: FrameState,
+}
+
+/// Captures an output, or a region of it, through `zwlr_screencopy_manager_v1`.
+/// The returned image is in physical pixels.
+pub fn capture_output(output_name: &str, region: Option<Rect>, overlay_cursor: bool) -> Result<RgbaImage> {
+    let conn = Connection::connect_to_env()
+        .context("Failed to connect to Wayland display")?;
+
+    let mut event_queue = conn.new_event_queue();
+    let qh = event_queue.handle();
+    let _registry = conn.display().get_registry(&qh, ());
+
+    let mut state = AppState::default();
+
+    // First roundtrip binds the globals, the second collects output names.
+    event_queue.roundtrip(&mut state)
+        .context("Failed to perform initial Wayland roundtrip")?;
+    event_queue.roundtrip(&mut state)
+        .context("Failed to query Wayland outputs")?;
+
+    let shm = state.shm.take()
+        .ok_or_else(|| anyhow!("wl_shm not found"))?;
+    let manager = state.manager.take()
+        .ok_or_else(|| anyhow!("Compositor does not support zwlr_screencopy_manager_v1"))?;
+    let output = state.outputs.iter()
+        .find(|(_, name)| name.as_deref() == Some(output_name))
+        .map(|(output, _)| output.clone())
+        .ok_or_else(|| anyhow!("Output {} not found", output_name))?;
+
+    let cursor = i32::from(overlay_cursor);
+    let frame = match region {
+        Some(r) => manager.capture_output_region(cursor, &output, r.x, r.y, r.width, r.height, &qh, ()),
+        None => manager.capture_output(cursor, &output, &qh, ()),
+    };
+
+    // Version 3 announces every buffer type followed by buffer_done; older
+    // versions only send the shm buffer event.
+    while !(state.buffer_done || (manager.version() < 3 && state.buffer_format.is_some())) {
+        if state.frame_state == FrameState::Failed {
+            bail!("Screencopy failed before a buffer was offered");
+        }
+        event_queue.blocking_dispatch(&mut state)?;
+    }
+
+    let format = state.buffer_format
+        .ok_or_else(|| anyhow!("Compositor offered no shm buffer for screencopy"))?;
+    let size = (format.stride * format.height) as usize;
+
+    let file = shm_file(size)?;
+    let mmap = unsafe { Mmap::map(&file) }
+        .context("Failed to map screencopy buffer")?;
+
+    let pool = shm.create_pool(file.as_fd(), size as i32, &qh, ());
+    let buffer = pool.create_buffer(
+        0,
+        format.width as i32,
+        format.height as i32,
+        format.stride as i32,
+        format.format,
+        &qh,
+        (),
+    );
+    frame.copy(&buffer);
+
+    while state.frame_state == FrameState::Pending {
+        event_queue.blocking_dispatch(&mut state)?;
+    }
+
+    frame.destroy();
+    buffer.destroy();
+    pool.destroy();
+
+    if state.frame_state == FrameState::Failed {
+        bail!("Screencopy of {} failed", output_name);
+    }
+
+    to_rgba(&mmap, format, state.y_invert)
+}
+
+/// Creates an unlinked file of `size` bytes to back a wl_shm pool.
+fn shm_file(size: usize) -> Result<File> {
+    let dir = env::var("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| env::temp_dir());
+    let path = dir.join(format!("lunactl-screencopy-{}", process::id()));
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    fs::remove_file(&path)?;
+    file.set_len(size as u64)?;
+    Ok(file)
+}
+
+/// Converts the raw shm buffer into an RGBA image.
+fn to_rgba(data: &[u8], format: ShmFormat, y_invert: bool) -> Result<RgbaImage> {
+    let ShmFormat { format, width, height, stride } = format;
+    let (swap_rb, has_alpha) = match format {
+        wl_shm::Format::Argb8888 => (true, true),
+        wl_shm::Format::Xrgb8888 => (true, false),
+        wl_shm::Format::Abgr8888 => (false, true),
+        wl_shm::Format::Xbgr8888 => (false, false),
+        other => bail!("Unsupported screencopy format: {:?}", other),
+    };
+
+    let row_len = (width * 4) as usize;
+    let mut pixels = Vec::with_capacity(row_len * height as usize);
+    for row in 0..height {
+        let row = if y_invert { height - 1 - row } else { row };
+        let start = (row * stride) as usize;
+        for px in data[start..start + row_len].chunks_exact(4) {
+            // Little-endian 32-bit formats: xRGB is stored as B, G, R, x.
+            let (r, b) = if swap_rb { (px[2], px[0]) } else { (px[0], px[2]) };
+            let a = if has_alpha { px[3] } else { 255 };
+            pixels.extend_from_slice(&[r, px[1], b, a]);
+        }
+    }
+
+    RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| anyhow!("Screencopy buffer has an unexpected size"))
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for AppState {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<AppState>,
+    ) {
+        if let wl_registry::Event::Global { name, interface, version } = event {
+            match interface.as_str() {
+                "wl_shm" => {
+                    state.shm = Some(registry.bind::<wl_shm::WlShm, (), _>(name, 1, qh, ()));
+                }
+                "wl_output" if version >= 4 => {
+                    let output = registry.bind::<wl_output::WlOutput, (), _>(name, 4, qh, ());
+                    state.outputs.push((output, None));
+                }
+                "zwlr_screencopy_manager_v1" => {
+                    state.manager = Some(registry.bind::<
+                        zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1,
+                        (),
+                        _,
+                    >(name, version.min(3), qh, ()));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, ()> for AppState {
+    fn event(
+        state: &mut Self,
+        output: &wl_output::WlOutput,
+        event: wl_output::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<AppState>,
+    ) {
+        if let wl_output::Event::Name { name } = event
+            && let Some(entry) = state.outputs.iter_mut().find(|(o, _)| o == output)
+        {
+            entry.1 = Some(name);
+        }
+    }
+}
+
+impl Dispatch<zwlr_screencopy_frame_v1::ZwlrScreencopyFrameV1, ()> for AppState {
+    fn event(
+        state: &mut Self,
+        _: &zwlr_screencopy_frame_v1::ZwlrScreencopyFrameV1,
+        event: zwlr_screencopy_frame_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<AppState>,
+    ) {
+        use zwlr_screencopy_frame_v1::Event;
+        match event {
+            Event::Buffer { format: WEnum::Value(format), width, height, stride } => {
+                state.buffer_format = Some(ShmFormat { format, width, height, stride });
+            }
+            Event::BufferDone => state.buffer_done = true,
+            Event::Flags { flags: WEnum::Value(flags) } => {
+                state.y_invert = flags.contains(zwlr_screencopy_frame_v1::Flags::YInvert);
+            }
+            Event::Ready { .. } => state.frame_state = FrameState::Ready,
+            Event::Failed => state.frame_state = FrameState::Failed,
+            _ => {}
+        }
+    }
+}
+
+// Implement Dispatch for other protocols to ignore their events
+impl Dispatch<wl_shm::WlShm, ()> for AppState {
+    fn event(
+        _state: &mut Self,
+        _: &wl_shm::WlShm,
+        _: wl_shm::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<AppState>,
+    ) {}
+}
+
+impl Dispatch<wl_shm_pool::WlShmPool, ()> for AppState {
+    fn event(
+        _state: &mut Self,
+        _: &wl_shm_pool::WlShmPool,
+        _: wl_shm_pool::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<AppState>,
+    ) {}
+}
+
+impl Dispatch<wl_buffer::WlBuffer, ()> for AppState {
+    fn event(
+        _state: &mut Self,
+        _: &wl_buffer::WlBuffer,
+        _: wl_buffer::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<AppState>,
+    ) {}
+}
+
+impl Dispatch<zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1, ()> for AppState {
+    fn event(
+        _state: &mut Self,
+        _: &zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1,
+        _: zwlr_screencopy_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<AppState>,
+    ) {}
+}
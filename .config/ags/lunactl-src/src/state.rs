@@ -0,0 +1,52 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// `$XDG_STATE_HOME/lunactl`, for data that should survive reboots.
+pub fn state_dir() -> Result<PathBuf> {
+    let base = match env::var("XDG_STATE_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var("HOME").context("HOME is not set")?).join(".local/state"),
+    };
+    ensure_dir(base.join("lunactl"))
+}
+
+/// `$XDG_RUNTIME_DIR/lunactl`, for sockets and files tied to the session.
+pub fn runtime_dir() -> Result<PathBuf> {
+    let base = env::var("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| env::temp_dir());
+    ensure_dir(base.join("lunactl"))
+}
+
+fn ensure_dir(dir: PathBuf) -> Result<PathBuf> {
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+    Ok(dir)
+}
+
+/// Reads a JSON file, returning `T::default()` if it does not exist yet.
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Writes a JSON file atomically, so concurrent readers never see a partial
+/// file. The temporary file is per process so concurrent writers do not
+/// rename each other's half-written files.
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let tmp = path.with_extension(format!("{}.tmp", process::id()));
+    fs::write(&tmp, serde_json::to_string_pretty(value)?)
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, path)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
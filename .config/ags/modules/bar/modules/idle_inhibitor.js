@@ -9,8 +9,8 @@ const configDir = Utils.CACHE_DIR.replace('cache/ags', 'config/ags');
 
 const checkIdleStatus = () => {
     try {
-        // Check if the idle inhibitor script is running
-        const isScriptRunning = !!exec('pidof -x lunactl');
+        // Ask the running idle inhibitor, if any, for its state
+        const isScriptRunning = JSON.parse(exec(`${LUNACTL_CMD} status --json`)).active;
         // Check if DPMS is enabled
         const dpmsStatus = exec('hyprctl getoption dpms').includes('int: 1');
         return isScriptRunning || !dpmsStatus;
@@ -41,14 +41,14 @@ const IdleInhibitor = ({ className = '', ...props } = {}) => {
                 
                 if (newState) {
                     console.log('Starting idle inhibitor...');
-                    await execAsync(['bash', '-c', LUNACTL_CMD]).catch(error => {
+                    await execAsync(['bash', '-c', `${LUNACTL_CMD} on`]).catch(error => {
                         console.error('Failed to start idle inhibitor:', error);
                         throw error;
                     });
                     console.log('Idle inhibitor started');
                 } else {
                     console.log('Stopping idle inhibitor...');
-                    await execAsync(['pkill', '-f', `lunactl idle-inhibitor`]).catch(error => {
+                    await execAsync(['bash', '-c', `${LUNACTL_CMD} off`]).catch(error => {
                         console.error('Failed to stop idle inhibitor:', error);
                         throw error;
                     });
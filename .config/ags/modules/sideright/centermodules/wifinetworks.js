@@ -77,28 +77,29 @@ const WifiNetwork = (accessPoint) => {
     })
 }
 
-const NetResource = (icon, command) => {
-    const resourceLabel = Label({
-        className: `txt-smaller txt-subtext`,
-    });
-    const widget = Button({
-        child: Box({
-            hpack: 'start',
-            className: `spacing-h-4`,
-            children: [
-                MaterialIcon(icon, 'very-small'),
-                resourceLabel,
-            ],
-            setup: (self) => self.poll(2000, () => execAsync(['bash', '-c', command])
-                .then((output) => {
-                    resourceLabel.label = output;
-                }).catch(print))
-            ,
-        })
-    });
-    return widget;
+// Same format as `lunactl network bandwidth` prints without --json
+const formatBandwidth = (rate) => {
+    const units = ['B/s', 'KB/s', 'MB/s', 'GB/s', 'TB/s'];
+    if (rate < 1024) return `${Math.round(rate)} ${units[0]}`;
+    let i = 0;
+    while (rate >= 1024 && i < units.length - 1) {
+        rate /= 1024;
+        i++;
+    }
+    return `${rate.toFixed(1)} ${units[i]}`;
 }
 
+const NetResource = (icon, resourceLabel) => Button({
+    child: Box({
+        hpack: 'start',
+        className: `spacing-h-4`,
+        children: [
+            MaterialIcon(icon, 'very-small'),
+            resourceLabel,
+        ],
+    })
+});
+
 const CurrentNetwork = () => {
     let passwordVisible = false;
     let authLock = false;
@@ -126,15 +127,32 @@ const CurrentNetwork = () => {
             }),
         ]
     });
+    const sentLabel = Label({ className: 'txt-smaller txt-subtext' });
+    const recvLabel = Label({ className: 'txt-smaller txt-subtext' });
     const networkBandwidth = Box({
         vertical: true,
         hexpand: true,
         hpack: 'end',
         className: 'sidebar-wifinetworks-bandwidth',
         children: [
-            NetResource('arrow_warm_up', `lunactl network bandwidth sent`),
-            NetResource('arrow_cool_down', `lunactl network bandwidth recv`),
-        ]
+            NetResource('arrow_warm_up', sentLabel),
+            NetResource('arrow_cool_down', recvLabel),
+        ],
+        // One long-running sampler for both directions; it stops with the widget
+        setup: (self) => Utils.subprocess(
+            ['lunactl', 'network', 'bandwidth', '--watch', '--json', '--interval', '2000'],
+            (output) => {
+                try {
+                    const { total } = JSON.parse(output);
+                    sentLabel.label = formatBandwidth(total.tx);
+                    recvLabel.label = formatBandwidth(total.rx);
+                } catch (error) {
+                    print(error);
+                }
+            },
+            print,
+            self,
+        ),
     });
     // const networkStatus = Box({
     //     children: [Label({
@@ -203,15 +203,15 @@ export const ModuleIdleInhibitor = (props = {}) => {
             self.toggleClassName('sidebar-button-active', self.attribute.enabled);
 
             if (self.attribute.enabled) {
-                await execAsync(['bash', '-c', `pidof -x lunactl || ${scriptPath}`]).catch(print);
+                await execAsync(['bash', '-c', `${scriptPath} on`]).catch(print);
             } else {
-                await execAsync(`pkill -f "${scriptPath}"`).catch(print);
+                await execAsync(['bash', '-c', `${scriptPath} off`]).catch(print);
             }
         },
         child: MaterialIcon('coffee', 'norm'),
         setup: (self) => {
             setupCursorHover(self);
-            self.attribute.enabled = !!exec('pidof -x lunactl');
+            self.attribute.enabled = JSON.parse(exec(`${scriptPath} status --json`) || '{}').active === true;
             self.toggleClassName('sidebar-button-active', self.attribute.enabled);
         },
         ...props,